@@ -1,10 +1,9 @@
-use std::collections::HashMap;
 use std::fs::DirEntry;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
 use itertools::Itertools;
-use substring_search::substring;
-use substring_search::implementations::{_naive_substring, _naive_prereserve_substring, _naive_prereserve_iter_substring, _naive_prereserve_iter_fx_substring, _naive_prereserve_iter_fx_shorter_substring, _alternate_prereserve_iter_fx_substring, _naive_prereserve_iter_rolling_adler_shorter_substring, build_fx_substring, build_rolling_adler_substring, build_sip_substring, build_rolling_polynomial_substring, _naive_prereserve_iter_rolling_poly_shorter_substring};
+use substring_search::{fasta_sequence, substring, GeneralizedSuffixArray, Strategy, SubstringFinder};
+use substring_search::implementations::{build_fx_substring, build_rolling_adler_substring, build_sip_substring, build_rolling_polynomial_substring};
 use substring_search::helpers::preprocess_string;
 
 #[derive(Clone)]
@@ -13,6 +12,19 @@ struct File {
     path: PathBuf,
 }
 
+// Reads a test file. Note: we only preprocess the strings in the benchmarks (rather than in the
+// substring functions themselves) to preserve generality. The genomes are FASTA files, so their
+// headers and newlines are stripped with `fasta_sequence()` instead, which leaves only bases and
+// lets `Strategy::PackedDna` search them with packed k-mers.
+fn read_test_file(path: &Path) -> String {
+    let text = std::fs::read_to_string(path).unwrap();
+    if path.parent().and_then(|p| p.file_name()).is_some_and(|cat| cat == "genomes") {
+        fasta_sequence(&text)
+    } else {
+        preprocess_string(&text)
+    }
+}
+
 pub fn bench_substring(c: &mut Criterion) {
     // Simple test
     c.bench_function("Simple substring", |b| b.iter(|| {
@@ -36,10 +48,8 @@ pub fn bench_substring(c: &mut Criterion) {
         test_files.iter().tuple_combinations().for_each(|(f1, f2)| {
             let f1_name = f1.file_name();
             let f2_name = f2.file_name();
-            // Note: we only preprocess the strings in the benchmarks (rather than in the substring
-            // functions themselves) to preserve generality.
-            let s1 = preprocess_string(&std::fs::read_to_string(f1.path()).unwrap());
-            let s2 = preprocess_string(&std::fs::read_to_string(f2.path()).unwrap());
+            let s1 = read_test_file(&f1.path());
+            let s2 = read_test_file(&f2.path());
             c.bench_function(&format!("{}_{}", f1_name.to_str().unwrap(), f2_name.to_str().unwrap()), |b| b.iter(|| {
                 substring(black_box(&s1), black_box(&s2), black_box(20))
             }));
@@ -47,16 +57,9 @@ pub fn bench_substring(c: &mut Criterion) {
     })
 }
 
-pub fn bench_substring_impls<'a>(c: &mut Criterion) {
+pub fn bench_substring_impls(c: &mut Criterion) {
     let mut group = c.benchmark_group("Substring");
 
-    // TODO(klinvill): I'm running into trouble making this function range over the functions. Worth
-    //  later investigation.
-    // let functions: HashMap<_, _> = HashMap::from([
-    //     ("naive_substring", _naive_substring as fn(&'a str, &'a str, usize) -> Option<&'a str>),
-    //     ("naive_prereserve_substring", _naive_prereserve_substring as fn(&'a str, &'a str, usize) -> Option<&'a str>),
-    // ]);
-
     // The test files are stored in the path format: data/<category>/<file>
     let test_categories = std::fs::read_dir("./data").unwrap()
         .filter(|d| {
@@ -79,119 +82,50 @@ pub fn bench_substring_impls<'a>(c: &mut Criterion) {
     }).collect();
 
     // Simple test
-    group.bench_function(BenchmarkId::new("naive_substring", "simple_5"), |b| b.iter(|| {
-        let s1 = "This is a test string. - Normal Person";
-        let s2 = "Here be another test string. Yaargh. - Pirate";
-        _naive_substring(black_box(s1), black_box(s2), black_box(5))
-    }));
-    group.bench_function(BenchmarkId::new("naive_prereserve_substring", "simple_5"), |b| b.iter(|| {
-        let s1 = "This is a test string. - Normal Person";
-        let s2 = "Here be another test string. Yaargh. - Pirate";
-        _naive_prereserve_substring(black_box(s1), black_box(s2), black_box(5))
-    }));
-    group.bench_function(BenchmarkId::new("naive_prereserve_iter_substring", "simple_5"), |b| b.iter(|| {
-        let s1 = "This is a test string. - Normal Person";
-        let s2 = "Here be another test string. Yaargh. - Pirate";
-        _naive_prereserve_iter_substring(black_box(s1), black_box(s2), black_box(5))
-    }));
-    group.bench_function(BenchmarkId::new("naive_prereserve_iter_fx_substring", "simple_5"), |b| b.iter(|| {
-        let s1 = "This is a test string. - Normal Person";
-        let s2 = "Here be another test string. Yaargh. - Pirate";
-        _naive_prereserve_iter_fx_substring(black_box(s1), black_box(s2), black_box(5))
-    }));
-    group.bench_function(BenchmarkId::new("naive_prereserve_iter_fx_shorter_substring", "simple_5"), |b| b.iter(|| {
-        let s1 = "This is a test string. - Normal Person";
-        let s2 = "Here be another test string. Yaargh. - Pirate";
-        _naive_prereserve_iter_fx_shorter_substring(black_box(s1), black_box(s2), black_box(5))
-    }));
-    group.bench_function(BenchmarkId::new("naive_prereserve_iter_rolling_adler_shorter_substring", "simple_5"), |b| b.iter(|| {
-        let s1 = "This is a test string. - Normal Person";
-        let s2 = "Here be another test string. Yaargh. - Pirate";
-        _naive_prereserve_iter_rolling_adler_shorter_substring(black_box(s1), black_box(s2), black_box(5))
-    }));
-    group.bench_function(BenchmarkId::new("naive_prereserve_iter_rolling_poly_shorter_substring", "simple_5"), |b| b.iter(|| {
-        let s1 = "This is a test string. - Normal Person";
-        let s2 = "Here be another test string. Yaargh. - Pirate";
-        _naive_prereserve_iter_rolling_poly_shorter_substring(black_box(s1), black_box(s2), black_box(5))
-    }));
-    group.bench_function(BenchmarkId::new("alternate_prereserve_iter_fx_substring", "simple_5"), |b| b.iter(|| {
-        let s1 = "This is a test string. - Normal Person";
-        let s2 = "Here be another test string. Yaargh. - Pirate";
-        _alternate_prereserve_iter_fx_substring(black_box(s1), black_box(s2), black_box(5))
-    }));
+    for finder in Strategy::ALL {
+        group.bench_function(BenchmarkId::new(finder.name(), "simple_5"), |b| b.iter(|| {
+            let s1 = "This is a test string. - Normal Person";
+            let s2 = "Here be another test string. Yaargh. - Pirate";
+            finder.find(black_box(s1), black_box(s2), black_box(5))
+        }));
+    }
+
+    // Note(klinvill): The adler benchmarks are extremely slow (take at least an hour each to run
+    // for the larger files) so they're skipped for the files below.
+    let file_finders: Vec<Strategy> = Strategy::ALL.into_iter()
+        .filter(|finder| *finder != Strategy::RollingAdler)
+        .collect();
 
     for (f1, f2) in test_file_pairs {
-        // Note: we only preprocess the strings in the benchmarks (rather than in the substring
-        // functions themselves) to preserve generality.
-        let s1 = preprocess_string(&std::fs::read_to_string(f1.path.clone()).unwrap());
-        let s2 = preprocess_string(&std::fs::read_to_string(f2.path.clone()).unwrap());
+        let s1 = read_test_file(&f1.path);
+        let s2 = read_test_file(&f2.path);
 
         // Note: 320 characters should be enough to make sure the project gutenberg headers don't trivially match
         for k in [5, 10, 20, 40, 80, 160, 320] {
-            group.bench_with_input(
-                BenchmarkId::new("naive_substring", &format!("{}_{}_{}", f1.name, f2.name, k)),
-                &(&s1, &s2),
-                |b, (s_1, s_2)| b.iter(|| {
-                    _naive_substring(black_box(s_1), black_box(s_2), black_box(k))
-                })
-            );
-            group.bench_with_input(
-                BenchmarkId::new("naive_prereserve_substring", &format!("{}_{}_{}", f1.name, f2.name, k)),
-                &(&s1, &s2),
-                |b, (s_1, s_2)| b.iter(|| {
-                    _naive_prereserve_substring(black_box(s_1), black_box(s_2), black_box(k))
-                })
-            );
-            group.bench_with_input(
-                BenchmarkId::new("naive_prereserve_iter_substring", &format!("{}_{}_{}", f1.name, f2.name, k)),
-                &(&s1, &s2),
-                |b, (s_1, s_2)| b.iter(|| {
-                    _naive_prereserve_iter_substring(black_box(s_1), black_box(s_2), black_box(k))
-                })
-            );
-            group.bench_with_input(
-                BenchmarkId::new("naive_prereserve_iter_fx_substring", &format!("{}_{}_{}", f1.name, f2.name, k)),
-                &(&s1, &s2),
-                |b, (s_1, s_2)| b.iter(|| {
-                    _naive_prereserve_iter_fx_substring(black_box(s_1), black_box(s_2), black_box(k))
-                })
-            );
-            group.bench_with_input(
-                BenchmarkId::new("naive_prereserve_iter_fx_shorter_substring", &format!("{}_{}_{}", f1.name, f2.name, k)),
-                &(&s1, &s2),
-                |b, (s_1, s_2)| b.iter(|| {
-                    _naive_prereserve_iter_fx_shorter_substring(black_box(s_1), black_box(s_2), black_box(k))
-                })
-            );
-            // Note(klinvill): The adler benchmarks are extremely slow (take at least an hour each
-            // to run for the larger files) so they've been commented out below.
-            //
-            // group.bench_with_input(
-            //     BenchmarkId::new("naive_prereserve_iter_rolling_adler_shorter_substring", &format!("{}_{}_{}", f1.name, f2.name, k)),
-            //     &(&s1, &s2),
-            //     |b, (s_1, s_2)| b.iter(|| {
-            //         _naive_prereserve_iter_rolling_adler_shorter_substring(black_box(s_1), black_box(s_2), black_box(k))
-            //     })
-            // );
-            group.bench_with_input(
-                BenchmarkId::new("naive_prereserve_iter_rolling_poly_shorter_substring", &format!("{}_{}_{}", f1.name, f2.name, k)),
-                &(&s1, &s2),
-                |b, (s_1, s_2)| b.iter(|| {
-                    _naive_prereserve_iter_rolling_poly_shorter_substring(black_box(s_1), black_box(s_2), black_box(k))
-                })
-            );
-            group.bench_with_input(
-                BenchmarkId::new("alternate_prereserve_iter_fx_substring", &format!("{}_{}_{}", f1.name, f2.name, k)),
-                &(&s1, &s2),
-                |b, (s_1, s_2)| b.iter(|| {
-                    _alternate_prereserve_iter_fx_substring(black_box(s_1), black_box(s_2), black_box(k))
-                })
-            );
+            for finder in &file_finders {
+                group.bench_with_input(
+                    BenchmarkId::new(finder.name(), format!("{}_{}_{}", f1.name, f2.name, k)),
+                    &(&s1, &s2),
+                    |b, (s_1, s_2)| b.iter(|| {
+                        finder.find(black_box(s_1), black_box(s_2), black_box(k))
+                    })
+                );
+            }
         }
+
+        // The suffix array doesn't depend on k, so it's built once for the whole sweep of k
+        group.bench_with_input(
+            BenchmarkId::new("generalized_suffix_array", format!("{}_{}_all_k", f1.name, f2.name)),
+            &(&s1, &s2),
+            |b, (s_1, s_2)| b.iter(|| {
+                let gsa = GeneralizedSuffixArray::new(black_box(s_1), black_box(s_2));
+                [5, 10, 20, 40, 80, 160, 320].map(|k| gsa.has_common_substring(black_box(k)))
+            })
+        );
     }
 }
 
-pub fn bench_hashes<'a>(c: &mut Criterion) {
+pub fn bench_hashes(c: &mut Criterion) {
     let mut group = c.benchmark_group("Hashes");
 
     // The test files are stored in the path format: data/<category>/<file>
@@ -248,14 +182,12 @@ pub fn bench_hashes<'a>(c: &mut Criterion) {
     }));
 
     for f in test_files {
-        // Note: we only preprocess the strings in the benchmarks (rather than in the substring
-        // functions themselves) to preserve generality.
-        let s = preprocess_string(&std::fs::read_to_string(f.path.clone()).unwrap());
+        let s = read_test_file(&f.path);
 
         // Note: 320 characters should be enough to make sure the project gutenberg headers don't trivially match
         for k in [5, 10, 20, 40, 80, 160, 320] {
             group.bench_with_input(
-                BenchmarkId::new("sip", &format!("{}_{}", f.name, k)),
+                BenchmarkId::new("sip", format!("{}_{}", f.name, k)),
                 &s,
                 |b, s| b.iter(|| {
                     let mut sub_fn = build_sip_substring(black_box(s), black_box(k));
@@ -265,7 +197,7 @@ pub fn bench_hashes<'a>(c: &mut Criterion) {
                 })
             );
             group.bench_with_input(
-                BenchmarkId::new("fx", &format!("{}_{}", f.name, k)),
+                BenchmarkId::new("fx", format!("{}_{}", f.name, k)),
                 &s,
                 |b, s| b.iter(|| {
                     let mut sub_fn = build_fx_substring(black_box(s), black_box(k));
@@ -275,7 +207,7 @@ pub fn bench_hashes<'a>(c: &mut Criterion) {
                 })
             );
             group.bench_with_input(
-                BenchmarkId::new("rolling_adler", &format!("{}_{}", f.name, k)),
+                BenchmarkId::new("rolling_adler", format!("{}_{}", f.name, k)),
                 &s,
                 |b, s| b.iter(|| {
                     let mut sub_fn = build_rolling_adler_substring(black_box(s), black_box(k));
@@ -285,7 +217,7 @@ pub fn bench_hashes<'a>(c: &mut Criterion) {
                 })
             );
             group.bench_with_input(
-                BenchmarkId::new("rolling_poly", &format!("{}_{}", f.name, k)),
+                BenchmarkId::new("rolling_poly", format!("{}_{}", f.name, k)),
                 &s,
                 |b, s| b.iter(|| {
                     let mut sub_fn = build_rolling_polynomial_substring(black_box(s), black_box(k));
@@ -0,0 +1,605 @@
+use std::ops::Range;
+use rustc_hash::FxHashMap;
+use crate::implementations::KGrams;
+
+/// A pair of similar (but not necessarily identical) regions found in two strings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApproxMatch<'a> {
+    /// The region of s1.
+    pub s1_text: &'a str,
+    /// The region of s2.
+    pub s2_text: &'a str,
+    /// Byte range of the region in s1.
+    pub s1_range: Range<usize>,
+    /// Byte range of the region in s2.
+    pub s2_range: Range<usize>,
+    /// Index (in characters) at which the region starts in s1.
+    pub s1_char_index: usize,
+    /// Index (in characters) at which the region starts in s2.
+    pub s2_char_index: usize,
+    /// Distance between the two regions: the number of mismatched characters for
+    /// `approx_common_substring()`, or the Levenshtein distance for `edit_similar_regions()`.
+    pub distance: usize,
+}
+
+impl<'a> ApproxMatch<'a> {
+    /// Swaps the regions of s1 and s2, for when the strings were searched in the opposite order.
+    fn swap(self) -> Self {
+        ApproxMatch {
+            s1_text: self.s2_text,
+            s2_text: self.s1_text,
+            s1_range: self.s2_range,
+            s2_range: self.s1_range,
+            s1_char_index: self.s2_char_index,
+            s2_char_index: self.s1_char_index,
+            distance: self.distance,
+        }
+    }
+}
+
+// The characters of a string along with their byte offsets, so regions can be compared by
+// characters and then sliced out of the string.
+struct Chars<'a> {
+    source: &'a str,
+    chars: Vec<char>,
+    // Byte offset of each character, followed by the length of the string
+    offsets: Vec<usize>,
+}
+
+impl<'a> Chars<'a> {
+    fn new(source: &'a str) -> Self {
+        let (mut offsets, chars): (Vec<usize>, Vec<char>) = source.char_indices().unzip();
+        offsets.push(source.len());
+        Chars { source, chars, offsets }
+    }
+
+    fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    fn region(&self, start: usize, len: usize) -> (&'a str, Range<usize>) {
+        let range = self.offsets[start]..self.offsets[start + len];
+        (&self.source[range.clone()], range)
+    }
+}
+
+// Builds the approximate match between the `s1_len` characters of s1 starting at `i` and the
+// `s2_len` characters of s2 starting at `j`.
+fn build_match<'a>(s1: &Chars<'a>, s2: &Chars<'a>, (i, s1_len): (usize, usize), (j, s2_len): (usize, usize), distance: usize) -> ApproxMatch<'a> {
+    let (s1_text, s1_range) = s1.region(i, s1_len);
+    let (s2_text, s2_range) = s2.region(j, s2_len);
+    ApproxMatch {
+        s1_text,
+        s2_text,
+        s1_range,
+        s2_range,
+        s1_char_index: i,
+        s2_char_index: j,
+        distance,
+    }
+}
+
+// Maps each q-length substring of `source` to the char indices of all its occurrences.
+fn build_seed_table(source: &str, q: usize) -> FxHashMap<&str, Vec<usize>> {
+    let mut seeds: FxHashMap<&str, Vec<usize>> = FxHashMap::default();
+    for (i, _, sub) in KGrams::new(source, q) {
+        seeds.entry(sub).or_default().push(i);
+    }
+    seeds
+}
+
+/// Given two strings, returns a pair of windows of k characters (one from each string) that differ
+/// in at most `max_mismatches` positions (i.e. whose Hamming distance is at most `max_mismatches`),
+/// or None if there is no such pair. Like `substring()`, this returns the first such window in the
+/// longer string (paired with the first matching window in the shorter string).
+///
+/// Uses pigeonhole seeding: if two windows differ in at most d positions, splitting them into d+1
+/// pieces leaves at least one piece without any mismatches. So every such pair of windows shares an
+/// exact substring of length k/(d+1) at the same offset. All the substrings of that length in the
+/// shorter string are stuck in a hash table (using the firefox hashing algorithm), then the
+/// substrings of the longer string are looked up in it to find seeds. Each seed is verified by
+/// sliding a window along its diagonal and counting the mismatches.
+pub fn approx_common_substring<'a>(s1: &'a str, s2: &'a str, k: usize, max_mismatches: usize) -> Option<ApproxMatch<'a>> {
+    let c1 = Chars::new(s1);
+    let c2 = Chars::new(s2);
+
+    // Impossible to have a window longer than the original strings.
+    if c1.len() < k || c2.len() < k {
+        return None;
+    }
+
+    // Choose shorter string to be the one we store in the hash table. The match needs to be
+    // swapped back if s2 was the shorter string.
+    if c1.len() <= c2.len() {
+        approx_shorter_substring(&c1, &c2, k, max_mismatches)
+    } else {
+        approx_shorter_substring(&c2, &c1, k, max_mismatches).map(|m| m.swap())
+    }
+}
+
+fn approx_shorter_substring<'a>(shorter: &Chars<'a>, longer: &Chars<'a>, k: usize, max_mismatches: usize) -> Option<ApproxMatch<'a>> {
+    let mismatch = |a: usize, b: usize| (shorter.chars[a] != longer.chars[b]) as usize;
+
+    // Any two windows are close enough when they are allowed to differ everywhere (this also covers
+    // k = 0).
+    if max_mismatches >= k {
+        let distance = (0..k).map(|o| mismatch(o, o)).sum();
+        return Some(build_match(shorter, longer, (0, k), (0, k), distance));
+    }
+
+    let q = k / (max_mismatches + 1);
+    let seeds = build_seed_table(shorter.source, q);
+
+    // Holds the (longer start, shorter start, distance) of the best pair of windows so far
+    let mut best: Option<(usize, usize, usize)> = None;
+    // The last window start in the longer string checked on each diagonal (identified by the
+    // difference between the window starts), so overlapping seeds don't check windows twice.
+    let mut checked: FxHashMap<isize, usize> = FxHashMap::default();
+
+    for (j, _, sub) in KGrams::new(longer.source, q) {
+        // Windows found from this seed onward can't start before j - (k - q) in the longer string.
+        if best.is_some_and(|(b, _, _)| j.saturating_sub(k - q) > b) {
+            break;
+        }
+        let Some(occurrences) = seeds.get(sub) else {
+            continue;
+        };
+
+        for &i in occurrences {
+            let diagonal = i as isize - j as isize;
+            // The windows that contain the seed at the same offset in both strings start at most
+            // k - q characters before it, and have to fit in both strings.
+            let mut b = j - (k - q).min(i).min(j);
+            let last = (j as isize).min((longer.len() - k) as isize).min((shorter.len() - k) as isize - diagonal);
+            if let Some(&c) = checked.get(&diagonal) {
+                b = b.max(c + 1);
+            }
+            if b as isize > last {
+                continue;
+            }
+            let last = last as usize;
+            checked.insert(diagonal, last);
+
+            let mut a = i + b - j;
+            let mut distance: usize = (0..k).map(|o| mismatch(a + o, b + o)).sum();
+            loop {
+                if distance <= max_mismatches {
+                    if best.is_none_or(|(best_b, best_a, _)| (b, a) < (best_b, best_a)) {
+                        best = Some((b, a, distance));
+                    }
+                    break;
+                }
+                if b == last {
+                    break;
+                }
+                distance = distance - mismatch(a, b) + mismatch(a + k, b + k);
+                a += 1;
+                b += 1;
+            }
+        }
+    }
+
+    best.map(|(b, a, distance)| build_match(shorter, longer, (a, k), (b, k), distance))
+}
+
+// Levenshtein distance between two sequences of characters, using the standard dynamic
+// programming algorithm with a single row.
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + (ca != cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// Finds the region of `text` that is closest (in Levenshtein distance) to `pattern`, when the
+// region is expected to start around char index `b0`. Only regions starting at most `max_edits`
+// characters away from `b0` (and no earlier than `min_start`) are considered, and only alignments
+// that stay within a band around that diagonal are computed, so this takes ~O(k * max_edits) time.
+// Returns the (start, end, distance) of the region, or None if it's more than `max_edits` edits
+// away.
+fn banded_edit_distance(pattern: &[char], text: &[char], b0: isize, max_edits: usize, min_start: usize) -> Option<(usize, usize, usize)> {
+    const UNREACHABLE: usize = usize::MAX / 2;
+    // An alignment within max_edits edits strays at most max_edits characters from the diagonal it
+    // starts on, which is itself at most max_edits characters away from b0.
+    let band = 2 * max_edits as isize;
+    let width = (2 * band + 1) as usize;
+    let column = |r: usize, d: usize| b0 + r as isize + d as isize - band;
+
+    // Each cell holds the distance and the start of the region for the best alignment ending there
+    let mut prev = vec![(UNREACHABLE, 0); width];
+    for (d, cell) in prev.iter_mut().enumerate() {
+        let c = column(0, d);
+        if (c - b0).unsigned_abs() <= max_edits && c >= min_start as isize && c <= text.len() as isize {
+            *cell = (0, c as usize);
+        }
+    }
+    // Insertions of text characters before the first pattern character
+    for d in 1..width {
+        if prev[d - 1].0 + 1 < prev[d].0 {
+            prev[d] = (prev[d - 1].0 + 1, prev[d - 1].1);
+        }
+    }
+
+    let mut cur = vec![(UNREACHABLE, 0); width];
+    for r in 1..=pattern.len() {
+        for d in 0..width {
+            let c = column(r, d);
+            cur[d] = (UNREACHABLE, 0);
+            if c < 0 || c > text.len() as isize {
+                continue;
+            }
+            let c = c as usize;
+            // Substitution (or match) of the characters before this cell
+            if c > 0 {
+                let (distance, start) = prev[d];
+                cur[d] = (distance + (pattern[r - 1] != text[c - 1]) as usize, start);
+            }
+            // Deletion of the pattern character
+            if d + 1 < width && prev[d + 1].0 + 1 < cur[d].0 {
+                cur[d] = (prev[d + 1].0 + 1, prev[d + 1].1);
+            }
+            // Insertion of the text character
+            if d > 0 && cur[d - 1].0 + 1 < cur[d].0 {
+                cur[d] = (cur[d - 1].0 + 1, cur[d - 1].1);
+            }
+        }
+        std::mem::swap(&mut prev, &mut cur);
+        // Distances never decrease from one row to the next
+        if prev.iter().all(|&(distance, _)| distance > max_edits) {
+            return None;
+        }
+    }
+
+    (0..width)
+        .filter(|&d| prev[d].0 <= max_edits)
+        .map(|d| (prev[d].1, column(pattern.len(), d) as usize, prev[d].0))
+        .min_by_key(|&(start, end, distance)| (distance, start, end))
+}
+
+/// Given two strings, returns pairs of similar regions that are at most `max_edits` insertions,
+/// deletions or substitutions apart (i.e. whose Levenshtein distance is at most `max_edits`). The
+/// region in the shorter string is always k characters long, while the region in the longer string
+/// is between k - `max_edits` and k + `max_edits` characters long. The regions reported in the
+/// longer string don't overlap and are sorted by their position in it.
+///
+/// When `max_edits` is at least k, every pair of regions is similar, so the longer string is just
+/// split into consecutive regions of k characters, each paired with the first window of the shorter
+/// string.
+///
+/// Uses q-gram filtering: if two regions are at most e edits apart, each edit touches at most q of
+/// the q-grams of the one of k characters, so at least k + 1 - (e+1)q of them occur exactly in the
+/// other region, on e+1 consecutive diagonals. All the substrings of length (k+1)/(e+2) in the
+/// shorter string are stuck in a hash table (using the firefox hashing algorithm), then the
+/// substrings of the longer string are looked up in it to find seeds, which are counted per bin of
+/// diagonals. Once a bin has enough seeds close to each other, the windows of the shorter string
+/// around the last seed are compared against the longer string using a banded edit distance
+/// computation around the seed's diagonal (skipping the windows already compared on that
+/// diagonal). The longer string is scanned in order, and the closest pair of regions found for each
+/// seed is reported.
+pub fn edit_similar_regions<'a>(s1: &'a str, s2: &'a str, k: usize, max_edits: usize) -> Vec<ApproxMatch<'a>> {
+    let c1 = Chars::new(s1);
+    let c2 = Chars::new(s2);
+
+    // Impossible to have a window longer than the original strings.
+    if c1.len() < k || c2.len() < k {
+        return Vec::new();
+    }
+
+    // Choose shorter string to be the one we store in the hash table. The matches need to be
+    // swapped back if s2 was the shorter string.
+    if c1.len() <= c2.len() {
+        edit_shorter_regions(&c1, &c2, k, max_edits)
+    } else {
+        edit_shorter_regions(&c2, &c1, k, max_edits).into_iter().map(|m| m.swap()).collect()
+    }
+}
+
+fn edit_shorter_regions<'a>(shorter: &Chars<'a>, longer: &Chars<'a>, k: usize, max_edits: usize) -> Vec<ApproxMatch<'a>> {
+    // Trivial to have matching regions of length 0
+    if k == 0 {
+        return vec![build_match(shorter, longer, (0, 0), (0, 0), 0)];
+    }
+
+    // Any two regions of k characters are close enough when they are allowed to differ everywhere,
+    // so the longer string is just split into regions of k characters (each paired with the first
+    // window of the shorter string).
+    if max_edits >= k {
+        let window = &shorter.chars[..k];
+        return (0..=(longer.len() - k)).step_by(k)
+            .map(|b| build_match(shorter, longer, (0, k), (b, k), levenshtein(window, &longer.chars[b..b + k])))
+            .collect();
+    }
+
+    // Each edit touches at most q of the q-grams of a window, so at least `threshold` of them are
+    // shared by two regions that are at most max_edits edits apart. With q-grams short enough that
+    // max_edits + 2 of them fit in a window, that's at least q of them, which lets most seeds be
+    // discarded without comparing any windows (exact matches only need a single k-gram).
+    let q = if max_edits == 0 { k } else { (k + 1) / (max_edits + 2) };
+    let threshold = k + 1 - (max_edits + 1) * q;
+    let seeds = build_seed_table(shorter.source, q);
+
+    let mut regions = Vec::new();
+    // Reported regions of the longer string can't start before the end of the previous one
+    let mut next_free = 0;
+    // The shared q-grams of two similar regions all lie on max_edits + 1 consecutive diagonals, and
+    // within k + max_edits - q characters of each other in the longer string. Diagonals are grouped
+    // into overlapping bins of 2 * (max_edits + 1) diagonals, which count the seeds found on them
+    // in the current and previous chunks of that many characters of the longer string.
+    let bin_width = max_edits + 1;
+    let span = k + max_edits - q;
+    let mut bins = vec![Bin::default(); (shorter.len() + longer.len()) / bin_width + 2];
+    // The last window start in the shorter string checked on each diagonal (identified by the
+    // difference between the seed positions), so overlapping seeds don't check windows twice.
+    let mut checked: FxHashMap<isize, usize> = FxHashMap::default();
+
+    for (j, _, sub) in KGrams::new(longer.source, q) {
+        let Some(occurrences) = seeds.get(sub) else {
+            continue;
+        };
+
+        // Holds the (distance, start, end, window start) of the closest pair of regions found from
+        // this seed
+        let mut best: Option<(usize, usize, usize, usize)> = None;
+        for &i in occurrences {
+            let diagonal = j as isize - i as isize;
+            if threshold > 1 {
+                let bin = (diagonal + shorter.len() as isize) as usize / bin_width + 1;
+                let chunk = j / span;
+                // Only the last shared q-gram of a pair of regions needs to find them
+                let found = bins[bin - 1].add(chunk).max(bins[bin].add(chunk));
+                if found < threshold {
+                    continue;
+                }
+            }
+
+            // The windows of the shorter string that contain the seed start at most k - q characters
+            // before it. Seeds on the same diagonal are found in order, so the windows up to the
+            // last one checked on it were already compared.
+            let mut first = i.saturating_sub(k - q);
+            let last = i.min(shorter.len() - k);
+            if let Some(&c) = checked.get(&diagonal) {
+                first = first.max(c + 1);
+            }
+            if first > last {
+                continue;
+            }
+            checked.insert(diagonal, last);
+
+            for a in first..=last {
+                let window = &shorter.chars[a..a + k];
+                if let Some((start, end, distance)) = banded_edit_distance(window, &longer.chars, a as isize + diagonal, max_edits, next_free) {
+                    if best.is_none_or(|closest| (distance, start, end, a) < closest) {
+                        best = Some((distance, start, end, a));
+                    }
+                }
+            }
+        }
+
+        if let Some((distance, start, end, a)) = best {
+            regions.push(build_match(shorter, longer, (a, k), (start, end - start), distance));
+            next_free = end;
+        }
+    }
+    regions
+}
+
+// Counts the seeds found on a bin of diagonals, in the current and previous chunks of the longer
+// string
+#[derive(Clone, Copy, Default)]
+struct Bin {
+    chunk: u32,
+    count: u16,
+    previous: u16,
+}
+
+impl Bin {
+    // Adds a seed found in the given chunk, and returns the number of seeds found in it and the
+    // previous one.
+    fn add(&mut self, chunk: usize) -> usize {
+        let chunk = chunk as u32;
+        if self.chunk != chunk {
+            self.previous = if self.chunk + 1 == chunk { self.count } else { 0 };
+            self.chunk = chunk;
+            self.count = 0;
+        }
+        self.count = self.count.saturating_add(1);
+        self.count as usize + self.previous as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use crate::approximate::{approx_common_substring, banded_edit_distance, edit_similar_regions, levenshtein};
+
+    // Reference implementation that compares every pair of windows. Returns the (s1 char index, s2
+    // char index, distance) of the first pair of windows in the longer string.
+    fn approx_common_substring_reference_impl(s1: &str, s2: &str, k: usize, max_mismatches: usize) -> Option<(usize, usize, usize)> {
+        let c1: Vec<char> = s1.chars().collect();
+        let c2: Vec<char> = s2.chars().collect();
+        let s1_shorter = c1.len() <= c2.len();
+        let (shorter, longer) = if s1_shorter {(&c1, &c2)} else {(&c2, &c1)};
+        if shorter.len() < k {
+            return None;
+        }
+        for b in 0..=(longer.len() - k) {
+            for a in 0..=(shorter.len() - k) {
+                let distance = (0..k).filter(|&o| shorter[a + o] != longer[b + o]).count();
+                if distance <= max_mismatches {
+                    return Some(if s1_shorter {(a, b, distance)} else {(b, a, distance)});
+                }
+            }
+        }
+        None
+    }
+
+    // Returns if any window of k characters of the shorter string is at most `max_edits` edits
+    // away from some region of the longer string.
+    fn has_edit_similar_regions_reference_impl(s1: &str, s2: &str, k: usize, max_edits: usize) -> bool {
+        let c1: Vec<char> = s1.chars().collect();
+        let c2: Vec<char> = s2.chars().collect();
+        let (shorter, longer) = if c1.len() <= c2.len() {(&c1, &c2)} else {(&c2, &c1)};
+        if shorter.len() < k {
+            return false;
+        }
+        (0..=(shorter.len() - k)).any(|a| {
+            (0..=longer.len()).any(|b| {
+                (b..=longer.len()).any(|end| levenshtein(&shorter[a..a + k], &longer[b..end]) <= max_edits)
+            })
+        })
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(levenshtein(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(levenshtein(&chars(""), &chars("abc")), 3);
+        assert_eq!(levenshtein(&chars("€10"), &chars("€10")), 0);
+    }
+
+    #[test]
+    fn test_edit_similar_regions() {
+        let s1 = "The quick brown fox jumps over the lazy dog";
+        let s2 = "A quick brown fox jumped over a lazy dog";
+
+        let regions: Vec<_> = edit_similar_regions(s1, s2, 15, 3).into_iter()
+            .map(|m| (m.s1_text, m.s2_text, m.distance))
+            .collect();
+        // The regions in s1 (the longer string) can be shorter than k because of deletions
+        assert_eq!(regions, vec![(" quick brown fo", " quick brown fo", 0), ("x jumps over ", "ox jumped over ", 3)]);
+        assert!(edit_similar_regions(s1, s2, 15, 0).iter().all(|m| m.s1_text == m.s2_text));
+    }
+
+    #[test]
+    fn test_edit_similar_regions_any_edits() {
+        let s1 = "abcdefgh";
+        let s2 = "xyz";
+
+        // Every pair of regions is similar, so s1 is just split into regions of k characters
+        let regions: Vec<_> = edit_similar_regions(s1, s2, 3, 3).into_iter()
+            .map(|m| (m.s1_text, m.s2_text, m.distance))
+            .collect();
+        assert_eq!(regions, vec![("abc", "xyz", 3), ("def", "xyz", 3)]);
+        assert!(edit_similar_regions(s1, s2, 4, 5).is_empty());
+    }
+
+    #[test]
+    fn test_approx_common_substring() {
+        let s1 = "ACGTTGCAACGTAGGCTTAC";
+        let s2 = "GGGGGTTGCTACGTAGGGGG";
+
+        let m = approx_common_substring(s1, s2, 10, 1).unwrap();
+        assert_eq!((m.s1_text, m.s2_text, m.distance), ("GTTGCAACGT", "GTTGCTACGT", 1));
+        assert_eq!((m.s1_range, m.s2_range), (2..12, 4..14));
+        assert_eq!(approx_common_substring(s1, s2, 10, 0), None);
+        assert_eq!(approx_common_substring(s1, s2, 0, 0).unwrap().distance, 0);
+    }
+
+    #[test]
+    fn test_approx_common_substring_multibyte() {
+        let s1 = "›It costs €10 for this item…";
+        let s2 = "It costs £10 for that";
+
+        let m = approx_common_substring(s1, s2, 14, 2).unwrap();
+        assert_eq!((m.s1_text, m.s2_text), ("It costs €10 f", "It costs £10 f"));
+        assert_eq!(&s1[m.s1_range], m.s1_text);
+        assert_eq!(m.s1_char_index, 1);
+    }
+
+    proptest! {
+        #[test]
+        fn test_approx_common_substring_against_reference(
+            s1 in "[ab€]{0,16}",
+            s2 in "[ab€]{0,16}",
+            k in 0..8usize,
+            max_mismatches in 0..4usize,
+        ) {
+            let r = approx_common_substring(&s1, &s2, k, max_mismatches);
+            assert_eq!(
+                r.as_ref().map(|m| (m.s1_char_index, m.s2_char_index, m.distance)),
+                approx_common_substring_reference_impl(&s1, &s2, k, max_mismatches)
+            );
+            if let Some(m) = r {
+                assert_eq!(&s1[m.s1_range], m.s1_text);
+                assert_eq!(&s2[m.s2_range], m.s2_text);
+            }
+        }
+
+        #[test]
+        fn test_banded_edit_distance_against_levenshtein(
+            pattern in "[ab€]{1,10}",
+            edits in prop::collection::vec((any::<prop::sample::Index>(), 0..3u8, "[ab€]"), 0..4),
+            prefix in "[ab€]{0,6}",
+            suffix in "[ab€]{0,6}",
+            max_edits in 0..4usize,
+        ) {
+            let pattern: Vec<char> = pattern.chars().collect();
+            // Apply random insertions, deletions and substitutions to a copy of the pattern, and
+            // surround it with random text
+            let mut region = pattern.clone();
+            for (index, op, c) in &edits {
+                let c = c.chars().next().unwrap();
+                let len = region.len();
+                match op {
+                    0 => region.insert(index.index(len + 1), c),
+                    _ if len == 0 => {}
+                    1 => { region.remove(index.index(len)); }
+                    _ => region[index.index(len)] = c,
+                }
+            }
+            let b0 = prefix.chars().count();
+            let text: Vec<char> = prefix.chars().chain(region).chain(suffix.chars()).collect();
+
+            // The closest region starting at most max_edits characters away from b0
+            let expected = (b0.saturating_sub(max_edits)..=(b0 + max_edits).min(text.len()))
+                .flat_map(|start| (start..=text.len()).map(move |end| (start, end)))
+                .map(|(start, end)| levenshtein(&pattern, &text[start..end]))
+                .min()
+                .filter(|&distance| distance <= max_edits);
+            let found = banded_edit_distance(&pattern, &text, b0 as isize, max_edits, 0);
+            assert_eq!(found.map(|(_, _, distance)| distance), expected);
+            if let Some((start, end, distance)) = found {
+                assert!(start.abs_diff(b0) <= max_edits);
+                assert_eq!(levenshtein(&pattern, &text[start..end]), distance);
+            }
+            if edits.len() <= max_edits {
+                assert!(found.is_some());
+            }
+        }
+
+        #[test]
+        fn test_edit_similar_regions_against_reference(
+            s1 in "[ab€]{0,16}",
+            s2 in "[ab€]{0,16}",
+            k in 1..9usize,
+            max_edits in 0..3usize,
+        ) {
+            let regions = edit_similar_regions(&s1, &s2, k, max_edits);
+            assert_eq!(!regions.is_empty(), has_edit_similar_regions_reference_impl(&s1, &s2, k, max_edits));
+
+            let s1_shorter = s1.chars().count() <= s2.chars().count();
+            let mut next_free = 0;
+            for m in regions {
+                let (short_text, long_text, long_range) = if s1_shorter {
+                    (m.s1_text, m.s2_text, m.s2_range)
+                } else {
+                    (m.s2_text, m.s1_text, m.s1_range)
+                };
+                let short_chars: Vec<char> = short_text.chars().collect();
+                let long_chars: Vec<char> = long_text.chars().collect();
+                assert_eq!(short_chars.len(), k);
+                assert_eq!(levenshtein(&short_chars, &long_chars), m.distance);
+                assert!(m.distance <= max_edits);
+                assert!(long_range.start >= next_free);
+                next_free = long_range.end;
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::hashers::mix;
+
+/// Sizing of a `BlockedBloomFilter`. The defaults (10 bits per item and 7 hashes) give a false
+/// positive rate of about 1%.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BloomFilterConfig {
+    bits_per_item: usize,
+    num_hashes: u32,
+}
+
+impl Default for BloomFilterConfig {
+    fn default() -> Self {
+        BloomFilterConfig {
+            bits_per_item: 10,
+            num_hashes: 7,
+        }
+    }
+}
+
+impl BloomFilterConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of bits reserved in the filter for each item. More bits lower the false
+    /// positive rate but make the filter bigger (and so less likely to stay in the cache).
+    pub fn bits_per_item(mut self, bits_per_item: usize) -> Self {
+        self.bits_per_item = bits_per_item.max(1);
+        self
+    }
+
+    /// Sets the number of bits set in the filter for each item. The false positive rate is lowest
+    /// when this is about 0.7 times the number of bits per item, but every hash adds to the cost
+    /// of insertions and lookups.
+    pub fn num_hashes(mut self, num_hashes: u32) -> Self {
+        self.num_hashes = num_hashes.max(1);
+        self
+    }
+
+    /// Returns the number of bits reserved in the filter for each item.
+    pub fn get_bits_per_item(&self) -> usize {
+        self.bits_per_item
+    }
+
+    /// Returns the number of bits set in the filter for each item.
+    pub fn get_num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Returns the expected false positive rate of a standard Bloom filter with this sizing, i.e.
+    /// (1 - e^(-h/b))^h for h hashes and b bits per item. Blocked Bloom filters are slightly less
+    /// accurate since the items aren't spread evenly across the blocks.
+    pub fn false_positive_rate(&self) -> f64 {
+        let h = self.num_hashes as f64;
+        (1.0 - (-h / self.bits_per_item as f64).exp()).powf(h)
+    }
+}
+
+// Number of u64 words in each block. 8 words (512 bits) make up a typical cache line.
+const BLOCK_WORDS: usize = 8;
+const BLOCK_BITS: u64 = (BLOCK_WORDS * 64) as u64;
+
+/// Blocked Bloom filter over 64-bit hashes. Each item only sets bits within a single cache-line
+/// sized block, so an insertion or a lookup only touches one cache line. The filter never reports
+/// that an inserted hash is missing, but can report that a missing hash may be present (a false
+/// positive), see `BloomFilterConfig`.
+///
+/// Any of the `build_*_substring` hashers can be used to compute the hashes, as long as the same
+/// hasher (and salt) is used for insertions and lookups.
+pub struct BlockedBloomFilter {
+    blocks: Vec<[u64; BLOCK_WORDS]>,
+    num_hashes: u32,
+}
+
+impl BlockedBloomFilter {
+    /// Creates an empty filter sized to hold `n_items` items with the given config.
+    pub fn new(n_items: usize, config: BloomFilterConfig) -> Self {
+        let n_blocks = (n_items * config.bits_per_item).div_ceil(BLOCK_BITS as usize).max(1);
+        BlockedBloomFilter {
+            blocks: vec![[0; BLOCK_WORDS]; n_blocks],
+            num_hashes: config.num_hashes,
+        }
+    }
+
+    // Returns the block for the hash, and an iterator over the positions of the bits to set in
+    // that block.
+    fn positions(&self, hash: u64) -> (usize, impl Iterator<Item = u64>) {
+        // Some of the hashes the filter gets (like the 32-bit adler hashes or the rolling
+        // polynomial hashes with small salts) don't use all 64 bits well, so they're mixed before
+        // they are used to pick bits.
+        let mixed = mix(hash);
+        // Maps the hash to a block without a (slow) modulo operation
+        let block = ((mixed as u128 * self.blocks.len() as u128) >> 64) as usize;
+        // Derives the bit positions from two more hashes (using double hashing)
+        let bits = mix(mixed ^ 0x9e3779b97f4a7c15);
+        let (a, b) = (bits & 0xffffffff, (bits >> 32) | 1);
+        (block, (0..self.num_hashes as u64).map(move |i| a.wrapping_add(i.wrapping_mul(b)) % BLOCK_BITS))
+    }
+
+    /// Adds the hash to the filter.
+    pub fn insert(&mut self, hash: u64) {
+        let (block, positions) = self.positions(hash);
+        let block = &mut self.blocks[block];
+        for p in positions {
+            block[(p / 64) as usize] |= 1 << (p % 64);
+        }
+    }
+
+    /// Returns false if the hash was definitely never added to the filter, or true if it may have
+    /// been.
+    pub fn may_contain(&self, hash: u64) -> bool {
+        let (block, mut positions) = self.positions(hash);
+        let block = &self.blocks[block];
+        positions.all(|p| block[(p / 64) as usize] & (1 << (p % 64)) != 0)
+    }
+
+    /// Returns the size of the filter in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.blocks.len() * BLOCK_WORDS * 8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use crate::bloom::{BlockedBloomFilter, BloomFilterConfig};
+
+    #[test]
+    fn test_bloom_filter_sizing() {
+        let config = BloomFilterConfig::new();
+        assert_eq!(config.get_bits_per_item(), 10);
+        assert_eq!(config.get_num_hashes(), 7);
+        assert!(config.false_positive_rate() < 0.01);
+        assert!(config.bits_per_item(4).false_positive_rate() > 0.1);
+
+        assert_eq!(BlockedBloomFilter::new(0, config).size_in_bytes(), 64);
+        assert_eq!(BlockedBloomFilter::new(1000, config).size_in_bytes(), 1280);
+    }
+
+    #[test]
+    fn test_bloom_filter_false_positive_rate() {
+        let n = 10000;
+        let config = BloomFilterConfig::new();
+        let mut filter = BlockedBloomFilter::new(n, config);
+        // Sequential hashes, like the ones a rolling hash with a small salt produces
+        for i in 0..n as u64 {
+            filter.insert(i);
+        }
+
+        let false_positives = (n as u64..11 * n as u64).filter(|&i| filter.may_contain(i)).count();
+        assert!((false_positives as f64) < 2.0 * config.false_positive_rate() * (10 * n) as f64);
+    }
+
+    proptest! {
+        #[test]
+        fn test_bloom_filter_no_false_negatives(
+            hashes in prop::collection::vec(any::<u64>(), 0..200),
+            bits_per_item in 1..16usize,
+            num_hashes in 1..10u32,
+        ) {
+            let config = BloomFilterConfig::new().bits_per_item(bits_per_item).num_hashes(num_hashes);
+            let mut filter = BlockedBloomFilter::new(hashes.len(), config);
+            for &hash in &hashes {
+                filter.insert(hash);
+            }
+            assert!(hashes.iter().all(|&hash| filter.may_contain(hash)));
+        }
+    }
+}
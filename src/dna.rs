@@ -0,0 +1,533 @@
+use std::fmt;
+use std::ops::Range;
+use rustc_hash::FxHashMap;
+use crate::implementations::Match;
+
+/// Largest k supported by `dna_substring()`. Each base takes 2 bits, so a k-mer of up to 32 bases
+/// fits in a `u64`.
+pub const MAX_DNA_K: usize = 32;
+
+/// Reasons the inputs can't be searched as DNA sequences.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DnaError {
+    /// The input contains a character other than `A`, `C`, `G` or `T` (e.g. the header line of a
+    /// FASTA file, a newline or an ambiguous base). Only `dna_substring()` accepts unknown `N`
+    /// bases.
+    InvalidBase {
+        /// The offending character.
+        base: char,
+        /// Byte offset of the character in the input.
+        byte_offset: usize,
+    },
+    /// k-mers longer than `MAX_DNA_K` bases don't fit in a `u64`.
+    KTooLarge(usize),
+}
+
+impl fmt::Display for DnaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnaError::InvalidBase { base, byte_offset } => {
+                write!(f, "invalid DNA base {:?} at byte offset {}", base, byte_offset)
+            },
+            DnaError::KTooLarge(k) => write!(f, "k-mers of {} bases don't fit in 64 bits (at most {})", k, MAX_DNA_K),
+        }
+    }
+}
+
+impl std::error::Error for DnaError {}
+
+// Returns the 2-bit code of a base. The codes are ordered like the bases, so packed k-mers compare
+// like the k-mers themselves.
+#[inline]
+fn encode(base: u8) -> Option<u64> {
+    match base {
+        b'A' => Some(0),
+        b'C' => Some(1),
+        b'G' => Some(2),
+        b'T' => Some(3),
+        _ => None,
+    }
+}
+
+// Checks that `sequence` only contains the bases A, C, G and T (and the unknown base N if
+// `allow_unknown` is set).
+fn validate(sequence: &str, allow_unknown: bool) -> Result<(), DnaError> {
+    match sequence.bytes().position(|b| encode(b).is_none() && !(allow_unknown && b == b'N')) {
+        // Bases are single bytes, so the first byte that isn't one starts a character
+        Some(byte_offset) => Err(DnaError::InvalidBase {
+            base: sequence[byte_offset..].chars().next().unwrap(),
+            byte_offset,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Iterator over the k-mers of a validated DNA sequence packed into `u64`s (2 bits per base, the
+/// first base in the highest bits). Each item is the index at which the k-mer starts along with
+/// the packed k-mer. The window rolls along the sequence: each base is shifted in once, and the
+/// base that falls out of the window is masked off. The reverse complement of the current k-mer is
+/// rolled along with it (see `reverse_complement()`). K-mers that contain an unknown base can't be
+/// packed, so they are skipped (see `unknown_kmers()`).
+pub(crate) struct PackedKmers<'a> {
+    bases: std::slice::Iter<'a, u8>,
+    k: usize,
+    mask: u64,
+    kmer: u64,
+    // Reverse complement of the current k-mer. Bases are shifted in from the highest bits instead.
+    reverse: u64,
+    // Number of bases read so far
+    read: usize,
+    // Number of bases read since the last unknown base
+    run: usize,
+}
+
+impl<'a> PackedKmers<'a> {
+    pub(crate) fn new(sequence: &'a [u8], k: usize) -> Self {
+        assert!(k > 0 && k <= MAX_DNA_K);
+        PackedKmers {
+            bases: sequence.iter(),
+            k,
+            mask: u64::MAX >> (64 - 2 * k),
+            kmer: 0,
+            reverse: 0,
+            read: 0,
+            run: 0,
+        }
+    }
+
+    /// Returns the reverse complement of the k-mer last returned by `next()`, packed the same way.
+    pub(crate) fn reverse_complement(&self) -> u64 {
+        self.reverse
+    }
+}
+
+impl Iterator for PackedKmers<'_> {
+    type Item = (usize, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for &base in self.bases.by_ref() {
+            self.read += 1;
+            // The sequence is validated up front (see `validate()`), so anything but a base is an
+            // unknown base. The window starts over after it.
+            let Some(code) = encode(base) else {
+                self.run = 0;
+                continue;
+            };
+            self.kmer = (self.kmer << 2 | code) & self.mask;
+            // The complement of a base is 3 minus its code (A <-> T and C <-> G)
+            self.reverse = self.reverse >> 2 | (3 - code) << (2 * self.k - 2);
+            self.run += 1;
+            if self.run >= self.k {
+                return Some((self.read - self.k, self.kmer));
+            }
+        }
+        None
+    }
+}
+
+/// Given two DNA sequences, returns the first found common substring of k bases or None if no
+/// such substring exists. Returns an error if either sequence contains anything but the bases A,
+/// C, G, T and N (an unknown base, used in the gaps of assemblies), or if k is larger than
+/// `MAX_DNA_K`. Use `fasta_sequence()` to strip the headers and newlines from FASTA files first.
+///
+/// Works like `substring()`, and finds the same substring: the k-mers of the shorter sequence are
+/// stuck in a pre-reserved hash map, then the k-mers of the other sequence are looked up in order.
+/// The k-mers are packed into `u64`s (2 bits per base) as the window rolls along each sequence, so
+/// no slices are hashed and k-mers are compared as integers instead of strings. Since the packing
+/// is exact, there are no false positives to verify. The few k-mers that contain an N can't be
+/// packed, so they are kept in a separate hash map and compared as strings.
+pub fn dna_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Result<Option<Match<'a>>, DnaError> {
+    // Trivial to have matching substrings of length 0
+    if k == 0 {
+        return Ok(Some(Match::empty()));
+    }
+    if k > MAX_DNA_K {
+        return Err(DnaError::KTooLarge(k));
+    }
+    validate(s1, true)?;
+    validate(s2, true)?;
+
+    // Bases are single bytes, so lengths, byte offsets and char indices are all the same.
+    // Impossible to have a substring longer than the original strings.
+    if s1.len() < k || s2.len() < k {
+        return Ok(None);
+    }
+
+    // Choose shorter string to be the one we store in the hash table
+    let (shorter, longer) = if s1.len() <= s2.len() {(s1, s2)} else {(s2, s1)};
+    let (kmers, unknown) = build_kmer_table(shorter, k);
+
+    // k-mer found in both s1 and s2, can stop early.
+    let found = PackedKmers::new(longer.as_bytes(), k)
+        .find_map(|(j, kmer)| kmers.get(&kmer).map(|&i| (i, j)));
+    // Only a k-mer with an unknown base that occurs before the packed match can come first.
+    let end = found.map_or(longer.len(), |(_, j)| j);
+    let found = unknown_kmers(longer, k)
+        .take_while(|&j| j < end)
+        .find_map(|j| unknown.get(&longer[j..j + k]).map(|&i| (i, j)))
+        .or(found);
+
+    // The match positions need to be swapped back if s2 was the shorter string.
+    Ok(found.map(|(i, j)| {
+        let m = Match::new(&longer[j..j + k], (i, i), (j, j));
+        if s1.len() <= s2.len() { m } else { m.swap() }
+    }))
+}
+
+// Same as `dna_substring()`, but returns every distinct common k-mer, like
+// `common_substrings(s1, s2, k).distinct()`: in the order they first occur in the longer sequence,
+// at their first occurrence in both sequences.
+pub(crate) fn dna_common_substrings<'a>(s1: &'a str, s2: &'a str, k: usize) -> Result<Vec<Match<'a>>, DnaError> {
+    // Trivial to have matching substrings of length 0
+    if k == 0 {
+        return Ok(vec![Match::empty()]);
+    }
+    if k > MAX_DNA_K {
+        return Err(DnaError::KTooLarge(k));
+    }
+    validate(s1, true)?;
+    validate(s2, true)?;
+
+    if s1.len() < k || s2.len() < k {
+        return Ok(Vec::new());
+    }
+
+    let (shorter, longer) = if s1.len() <= s2.len() {(s1, s2)} else {(s2, s1)};
+    let (mut kmers, mut unknown) = build_kmer_table(shorter, k);
+
+    // Each k-mer is only returned once, so it is taken out of the table once found.
+    let mut found: Vec<(usize, usize)> = PackedKmers::new(longer.as_bytes(), k)
+        .filter_map(|(j, kmer)| kmers.remove(&kmer).map(|i| (i, j)))
+        .collect();
+    found.extend(unknown_kmers(longer, k).filter_map(|j| unknown.remove(&longer[j..j + k]).map(|i| (i, j))));
+    // The k-mers with an unknown base are found separately, so they need to be put back in order.
+    found.sort_unstable_by_key(|&(_, j)| j);
+
+    Ok(found.into_iter().map(|(i, j)| {
+        let m = Match::new(&longer[j..j + k], (i, i), (j, j));
+        if s1.len() <= s2.len() { m } else { m.swap() }
+    }).collect())
+}
+
+// Builds the hash maps from the k-mers of a validated sequence to the index at which they first
+// occur in it: one for the packed k-mers, and one for the k-mers that contain an unknown base.
+fn build_kmer_table(sequence: &str, k: usize) -> (FxHashMap<u64, usize>, FxHashMap<&str, usize>) {
+    // Note: we reserve space to guarantee that the hash map can hold at least `capacity` elements
+    // without reallocating.
+    let mut kmers: FxHashMap<u64, usize> = FxHashMap::default();
+    kmers.reserve(sequence.len() - k + 1);
+    for (i, kmer) in PackedKmers::new(sequence.as_bytes(), k) {
+        // We only keep track of where a k-mer first occurs in the sequence.
+        kmers.entry(kmer).or_insert(i);
+    }
+    let mut unknown: FxHashMap<&str, usize> = FxHashMap::default();
+    for i in unknown_kmers(sequence, k) {
+        unknown.entry(&sequence[i..i + k]).or_insert(i);
+    }
+    (kmers, unknown)
+}
+
+// Returns the indices at which the k-mers that contain an unknown base (the ones `PackedKmers`
+// skips) start, in order. The sequence needs to have at least k bases.
+fn unknown_kmers(sequence: &str, k: usize) -> impl Iterator<Item = usize> + '_ {
+    let last = sequence.len() - k;
+    // Index of the first k-mer that wasn't returned yet, so runs of Ns don't repeat k-mers
+    let mut next = 0;
+    sequence.bytes().enumerate().filter(|&(_, base)| base == b'N').flat_map(move |(p, _)| {
+        // The k-mers that start up to k - 1 bases before the N contain it
+        let start = next.max((p + 1).saturating_sub(k));
+        let end = p.min(last) + 1;
+        next = next.max(end);
+        start..end
+    })
+}
+
+/// Which strand of s2 a `CanonicalMatch` was found on, relative to s1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Strand {
+    /// The same sequence occurs in both strings.
+    Forward,
+    /// The sequence in s2 is the reverse complement of the one in s1, i.e. the sequence occurs on
+    /// the opposite strand of s2.
+    Reverse,
+}
+
+/// A k-mer found in two DNA sequences, either as is or as its reverse complement, along with
+/// where it occurs in each of them. Since bases are single bytes, the byte ranges are also the
+/// ranges of bases.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CanonicalMatch<'a> {
+    /// The k-mer as it occurs in s1.
+    pub s1_text: &'a str,
+    /// The k-mer as it occurs in s2. This is the reverse complement of `s1_text` for
+    /// `Strand::Reverse` matches.
+    pub s2_text: &'a str,
+    /// Byte range of the k-mer in s1.
+    pub s1_range: Range<usize>,
+    /// Byte range of the k-mer in s2.
+    pub s2_range: Range<usize>,
+    /// Which strand of s2 the k-mer was found on.
+    pub strand: Strand,
+}
+
+impl<'a> CanonicalMatch<'a> {
+    // Swaps the positions in s1 and s2. The strand doesn't change, since the reverse complement of
+    // the reverse complement is the k-mer itself.
+    fn swap(self) -> Self {
+        CanonicalMatch {
+            s1_text: self.s2_text,
+            s2_text: self.s1_text,
+            s1_range: self.s2_range,
+            s2_range: self.s1_range,
+            strand: self.strand,
+        }
+    }
+}
+
+/// Same as `dna_substring()`, but also finds k-mers that occur on opposite strands of the two
+/// sequences, i.e. where the k-mer of one sequence is the reverse complement of the k-mer of the
+/// other. The returned match reports which strand it was found on. Returns an error if either
+/// sequence contains anything but the bases A, C, G and T, or if k is larger than `MAX_DNA_K`.
+///
+/// Each k-mer is stored and looked up by its canonical form: the lexicographically smaller of
+/// itself and its reverse complement (which is the same for both strands). Both are rolled along
+/// the sequences as packed `u64`s, so finding the canonical form is a single comparison. The
+/// match returned is the first k-mer in the longer sequence whose canonical form occurs in the
+/// shorter one (on either strand), along with the first such occurrence in the shorter sequence.
+/// K-mers that are their own reverse complement are reported as `Strand::Forward`.
+pub fn canonical_dna_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Result<Option<CanonicalMatch<'a>>, DnaError> {
+    // Trivial to have matching substrings of length 0
+    if k == 0 {
+        return Ok(Some(CanonicalMatch {
+            s1_text: "",
+            s2_text: "",
+            s1_range: 0..0,
+            s2_range: 0..0,
+            strand: Strand::Forward,
+        }));
+    }
+    if k > MAX_DNA_K {
+        return Err(DnaError::KTooLarge(k));
+    }
+    validate(s1, false)?;
+    validate(s2, false)?;
+
+    // Impossible to have a substring longer than the original strings.
+    if s1.len() < k || s2.len() < k {
+        return Ok(None);
+    }
+
+    // Choose shorter string to be the one we store in the hash table
+    let (shorter, longer) = if s1.len() <= s2.len() {(s1, s2)} else {(s2, s1)};
+
+    // Maps each canonical k-mer to where it first occurs in the shorter string, and whether it
+    // occurs there as is (rather than as its reverse complement).
+    let mut kmers: FxHashMap<u64, (usize, bool)> = FxHashMap::default();
+    kmers.reserve(shorter.len() - k + 1);
+    let mut short_kmers = PackedKmers::new(shorter.as_bytes(), k);
+    while let Some((i, kmer)) = short_kmers.next() {
+        let canonical = kmer.min(short_kmers.reverse_complement());
+        kmers.entry(canonical).or_insert((i, kmer == canonical));
+    }
+
+    let mut long_kmers = PackedKmers::new(longer.as_bytes(), k);
+    while let Some((j, kmer)) = long_kmers.next() {
+        let canonical = kmer.min(long_kmers.reverse_complement());
+        if let Some(&(i, short_forward)) = kmers.get(&canonical) {
+            // Canonical k-mer found in both s1 and s2, can return early. The k-mers are on the same
+            // strand if both occur in the same orientation relative to their canonical form. The
+            // match positions need to be swapped back if s2 was the shorter string.
+            let strand = if short_forward == (kmer == canonical) { Strand::Forward } else { Strand::Reverse };
+            let m = CanonicalMatch {
+                s1_text: &shorter[i..i + k],
+                s2_text: &longer[j..j + k],
+                s1_range: i..i + k,
+                s2_range: j..j + k,
+                strand,
+            };
+            return Ok(Some(if s1.len() <= s2.len() { m } else { m.swap() }));
+        }
+    }
+
+    // No k-mer in the longer string is also in the shorter one (on either strand).
+    Ok(None)
+}
+
+/// Returns the reverse complement of a DNA sequence: the sequence of the opposite strand, read in
+/// the same direction. Returns an error if the sequence contains anything but the bases A, C, G
+/// and T.
+pub fn reverse_complement(sequence: &str) -> Result<String, DnaError> {
+    validate(sequence, false)?;
+    Ok(sequence.bytes().rev().map(|base| match base {
+        b'A' => 'T',
+        b'C' => 'G',
+        b'G' => 'C',
+        _ => 'A',
+    }).collect())
+}
+
+/// Returns the sequence stored in a FASTA file, without the header lines (starting with `>`) or
+/// comment lines (starting with `;`), and with the sequence lines joined back together. The
+/// sequences of files with several records are concatenated. Bases are upper-cased, since some
+/// files use lower case for soft-masked regions.
+pub fn fasta_sequence(text: &str) -> String {
+    let mut sequence = String::with_capacity(text.len());
+    for line in text.lines() {
+        if line.starts_with('>') || line.starts_with(';') {
+            continue;
+        }
+        sequence.extend(line.trim_end().chars().map(|c| c.to_ascii_uppercase()));
+    }
+    sequence
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use proptest::prelude::*;
+    use crate::dna::{canonical_dna_substring, dna_substring, fasta_sequence, reverse_complement, DnaError, PackedKmers, Strand};
+    use crate::substring;
+
+    #[test]
+    fn test_packed_kmers() {
+        let kmers: Vec<_> = PackedKmers::new(b"ACGTA", 3).collect();
+        assert_eq!(kmers, vec![(0, 0b000110), (1, 0b011011), (2, 0b101100)]);
+        // A full 64-bit window
+        let kmers: Vec<_> = PackedKmers::new("T".repeat(33).as_bytes(), 32).collect();
+        assert_eq!(kmers, vec![(0, u64::MAX), (1, u64::MAX)]);
+
+        // The reverse complements are rolled along with the k-mers
+        let mut kmers = PackedKmers::new(b"ACCTA", 3);
+        assert_eq!(kmers.next(), Some((0, 0b000101)));
+        // GGT
+        assert_eq!(kmers.reverse_complement(), 0b101011);
+        kmers.next();
+        // AGG
+        assert_eq!(kmers.reverse_complement(), 0b001010);
+    }
+
+    #[test]
+    fn test_reverse_complement() {
+        assert_eq!(reverse_complement("GATTACA"), Ok("TGTAATC".to_string()));
+        assert_eq!(reverse_complement(""), Ok(String::new()));
+        assert_eq!(reverse_complement("GANTACA"), Err(DnaError::InvalidBase { base: 'N', byte_offset: 2 }));
+    }
+
+    #[test]
+    fn test_canonical_dna_substring() {
+        let shared = "GAAGTCAAGACGAAATTAG";
+        let genome1 = format!("CCCCCCCC{}CCCCCC", shared);
+        // The shared sequence is on the opposite strand of the second genome
+        let genome2 = format!("CCCCCCCCCCCC{}CCCCCCCCCCCCCC", reverse_complement(shared).unwrap());
+
+        assert_eq!(dna_substring(&genome1, &genome2, 10), Ok(None));
+        let m = canonical_dna_substring(&genome1, &genome2, 10).unwrap().unwrap();
+        assert_eq!(m.strand, Strand::Reverse);
+        assert_eq!(m.s1_text, reverse_complement(m.s2_text).unwrap());
+        assert_eq!(&genome1[m.s1_range.clone()], m.s1_text);
+        assert_eq!(&genome2[m.s2_range.clone()], m.s2_text);
+        // The first 10-mer of the longer genome is the reverse complement of the last one of the
+        // shared sequence
+        assert_eq!(m.s2_range, 12..22);
+        assert_eq!(m.s1_range, 17..27);
+
+        // Same strand
+        let genome3 = format!("TT{}", shared);
+        let m = canonical_dna_substring(&genome1, &genome3, 10).unwrap().unwrap();
+        assert_eq!(m.strand, Strand::Forward);
+        assert_eq!(m.s1_text, m.s2_text);
+        assert_eq!(m.s1_range, 8..18);
+        assert_eq!(m.s2_range, 2..12);
+
+        assert_eq!(canonical_dna_substring(&genome1, &genome2, 20), Ok(None));
+        assert_eq!(canonical_dna_substring(&genome1, "ACGN", 2), Err(DnaError::InvalidBase { base: 'N', byte_offset: 3 }));
+    }
+
+    #[test]
+    fn test_dna_substring() {
+        let genome1 = "GAACGCGGCACACTTGATGAAGTCAAGACGAAATTAGACGTGCGG";
+        let genome2 = "TTTCAAGACAATTAATCAAGCCCACCTTACAGTGAAGTCAAGACGTTAAATA";
+
+        let m = dna_substring(genome1, genome2, 12).unwrap().unwrap();
+        assert_eq!(m.text, "TGAAGTCAAGAC");
+        assert_eq!(m.s1_range, 17..29);
+        assert_eq!(m.s2_range, 32..44);
+        assert_eq!(dna_substring(genome1, genome2, 14), Ok(None));
+        assert_eq!(dna_substring(genome1, genome2, 0), Ok(substring(genome1, genome2, 0)));
+
+        // k-mers with unknown bases only match the exact same k-mer
+        let genome3 = "ACGTNNNNACGTTNNA";
+        let m = dna_substring(genome3, "TTNNACG", 4).unwrap().unwrap();
+        assert_eq!(m.text, "NNAC");
+        assert_eq!(m.s1_range, 6..10);
+        assert_eq!(m.s2_range, 2..6);
+        assert_eq!(dna_substring(genome3, "NNNNN", 5), Ok(None));
+        assert_eq!(dna_substring(genome3, "GGTNNNNAC", 6).unwrap().map(|m| m.s1_range), Some(2..8));
+    }
+
+    #[test]
+    // The genomes used in the benchmarks should be searched with packed k-mers once the FASTA
+    // headers and newlines are stripped, rather than falling back to hashing substrings.
+    fn test_dna_substring_genomes() {
+        let genomes: Vec<String> = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/data/genomes")).unwrap()
+            .map(|f| fasta_sequence(&std::fs::read_to_string(f.unwrap().path()).unwrap()))
+            .collect();
+        assert!(genomes.len() >= 2);
+        for (g1, g2) in genomes.iter().tuple_combinations() {
+            assert!(dna_substring(g1, g2, 20).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_dna_errors() {
+        assert_eq!(dna_substring("ACGT", "ACGT", 33), Err(DnaError::KTooLarge(33)));
+        assert_eq!(dna_substring("ACGT", "ACRGT", 2), Err(DnaError::InvalidBase { base: 'R', byte_offset: 2 }));
+        assert_eq!(dna_substring("ACGT\n", "ACGT", 2), Err(DnaError::InvalidBase { base: '\n', byte_offset: 4 }));
+        assert_eq!(dna_substring("ACGT", "AC€", 2), Err(DnaError::InvalidBase { base: '€', byte_offset: 2 }));
+        assert_eq!(dna_substring("acgt", "ACGT", 2), Err(DnaError::InvalidBase { base: 'a', byte_offset: 0 }));
+        assert_eq!(DnaError::InvalidBase { base: 'N', byte_offset: 2 }.to_string(), "invalid DNA base 'N' at byte offset 2");
+    }
+
+    #[test]
+    fn test_fasta_sequence() {
+        let fasta = ">QBUL01000189.1 ANME-1 cluster archaeon\r\nGAACGCGG\r\nCACACT\r\n;comment\n>second record\nacgt\n";
+        assert_eq!(fasta_sequence(fasta), "GAACGCGGCACACTACGT");
+        assert_eq!(fasta_sequence(""), "");
+    }
+
+    proptest! {
+        #[test]
+        // The packed k-mers are exact, so the same substring as `substring()` should be found. The
+        // k-mers with unknown bases are compared as strings, which is exact too.
+        fn test_dna_substring_exact(
+            s1 in "[ACGTN]{0,40}",
+            s2 in "[ACGTN]{0,40}",
+            k in 0..8usize,
+        ) {
+            assert_eq!(dna_substring(&s1, &s2, k), Ok(substring(&s1, &s2, k)));
+        }
+
+        #[test]
+        // Checks the canonical matches against searching s1 for the k-mers of s2 and of its reverse
+        // complement.
+        fn test_canonical_dna_substring_against_reference(
+            s1 in "[ACGT]{0,30}",
+            s2 in "[ACGT]{0,30}",
+            k in 1..6usize,
+        ) {
+            let reverse2 = reverse_complement(&s2).unwrap();
+            let expected = substring(&s1, &s2, k).is_some() || substring(&s1, &reverse2, k).is_some();
+            let m = canonical_dna_substring(&s1, &s2, k).unwrap();
+            assert_eq!(m.is_some(), expected);
+            if let Some(m) = m {
+                assert_eq!(&s1[m.s1_range.clone()], m.s1_text);
+                assert_eq!(&s2[m.s2_range.clone()], m.s2_text);
+                assert_eq!(m.s1_text.len(), k);
+                match m.strand {
+                    Strand::Forward => assert_eq!(m.s1_text, m.s2_text),
+                    Strand::Reverse => assert_eq!(m.s1_text, reverse_complement(m.s2_text).unwrap()),
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,46 @@
-use itertools::enumerate;
 use rand::random;
 
+// Finalizer from MurmurHash3. Spreads the bits of a hash so that every input bit affects every
+// output bit, which lets hashes be used as (pseudo) random values.
+pub fn mix(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^ (hash >> 33)
+}
+
+// Mersenne prime used as the modulus of `RollingPolynomialMod61`.
+const MERSENNE_61: u64 = (1 << 61) - 1;
+
+// Returns `a * b` modulo 2^61 - 1, for `a` and `b` already reduced modulo 2^61 - 1.
+#[inline]
+fn mul_mod61(a: u64, b: u64) -> u64 {
+    let product = a as u128 * b as u128;
+    // 2^61 is 1 modulo 2^61 - 1, so the high bits can be folded onto the low ones.
+    let folded = (product as u64 & MERSENNE_61) + (product >> 61) as u64;
+    if folded >= MERSENNE_61 { folded - MERSENNE_61 } else { folded }
+}
+
+/// A hash over a window of bytes that can slide along a sequence: bytes are added at the end of
+/// the window and removed from its start.
+pub trait RollingHash {
+    /// Adds a byte to the end of the window.
+    fn update(&mut self, byte: u8);
+
+    /// Removes the first byte of a window of `size` bytes.
+    fn remove(&mut self, size: u32, byte: u8);
+
+    /// Returns the hash of the current window.
+    fn hash(&self) -> u64;
+
+    fn update_buffer(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.update(*b);
+        }
+    }
+}
+
 pub struct RollingPolynomial {
     hash: u64,
     salt: u64,
@@ -20,10 +60,7 @@ impl RollingPolynomial {
             // blow up quickly
             salt = random::<u8>() as u64;
         }
-        RollingPolynomial {
-            hash: 0,
-            salt,
-        }
+        Self::with_salt(salt)
     }
 
     pub fn with_salt(salt: u64) -> Self {
@@ -33,12 +70,22 @@ impl RollingPolynomial {
         }
     }
 
+    // Returns a random odd 64-bit salt. Unlike the byte-long salts from `new()`, these spread the
+    // hashes over all 64 bits, which is needed when the hashes are used as fingerprints. Odd salts
+    // are invertible modulo 2^64, so the first bytes of long windows are never shifted out of the
+    // hash (which happens with even salts).
+    pub fn random_odd_salt() -> u64 {
+        random::<u64>() | 1
+    }
+
+    #[allow(dead_code)]
     pub fn from_buffer(bytes: &[u8]) -> Self {
         let mut instance = Self::new();
         instance.update_buffer(bytes);
         instance
     }
 
+    #[allow(dead_code)]
     pub fn from_buffer_with_salt(bytes: &[u8], salt: u64) -> Self {
         let mut instance = Self::with_salt(salt);
         instance.update_buffer(bytes);
@@ -50,11 +97,11 @@ impl RollingPolynomial {
     }
 
     pub fn remove(&mut self, size: u32, byte: u8) {
-        self.hash = self.hash.wrapping_sub(((byte as u64).wrapping_mul(self.salt.wrapping_pow(size - 1))))
+        self.hash = self.hash.wrapping_sub((byte as u64).wrapping_mul(self.salt.wrapping_pow(size - 1)))
     }
 
     pub fn update_buffer(&mut self, bytes: &[u8]) {
-        for (i, b) in enumerate(bytes) {
+        for b in bytes {
             self.update(*b);
         }
     }
@@ -68,9 +115,80 @@ impl RollingPolynomial {
     }
 }
 
+impl RollingHash for RollingPolynomial {
+    fn update(&mut self, byte: u8) {
+        RollingPolynomial::update(self, byte)
+    }
+
+    fn remove(&mut self, size: u32, byte: u8) {
+        RollingPolynomial::remove(self, size, byte)
+    }
+
+    fn hash(&self) -> u64 {
+        RollingPolynomial::hash(self)
+    }
+}
+
+pub struct RollingPolynomialMod61 {
+    hash: u64,
+    salt: u64,
+}
+
+/// Same polynomial hash as `RollingPolynomial`, but computed modulo the prime 2^61 - 1 instead of
+/// modulo 2^64. Arithmetic modulo a prime is slower, but makes this a proper Karp-Rabin hash: for a
+/// random salt, two different windows of l bytes collide with probability at most l/2^61, whatever
+/// the inputs are. Hashes modulo 2^64 don't have such a bound (e.g. Thue-Morse sequences collide
+/// for every odd salt), so the two make independent halves of a larger fingerprint.
+impl RollingPolynomialMod61 {
+    pub fn with_salt(salt: u64) -> Self {
+        assert!(salt < MERSENNE_61, "the salt has to be reduced modulo 2^61 - 1");
+        RollingPolynomialMod61 {
+            hash: 0,
+            salt,
+        }
+    }
+
+    // Returns a random salt in [2, 2^61 - 2].
+    pub fn random_salt() -> u64 {
+        2 + random::<u64>() % (MERSENNE_61 - 3)
+    }
+
+    #[allow(dead_code)]
+    pub fn from_buffer_with_salt(bytes: &[u8], salt: u64) -> Self {
+        let mut instance = Self::with_salt(salt);
+        instance.update_buffer(bytes);
+        instance
+    }
+}
+
+impl RollingHash for RollingPolynomialMod61 {
+    fn update(&mut self, byte: u8) {
+        let hash = mul_mod61(self.hash, self.salt) + byte as u64;
+        self.hash = if hash >= MERSENNE_61 { hash - MERSENNE_61 } else { hash };
+    }
+
+    fn remove(&mut self, size: u32, byte: u8) {
+        // salt^(size-1) by repeated squaring
+        let (mut power, mut base, mut exponent) = (1, self.salt, size - 1);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                power = mul_mod61(power, base);
+            }
+            base = mul_mod61(base, base);
+            exponent >>= 1;
+        }
+        let removed = mul_mod61(byte as u64, power);
+        self.hash = if self.hash >= removed { self.hash - removed } else { self.hash + MERSENNE_61 - removed };
+    }
+
+    fn hash(&self) -> u64 {
+        self.hash
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::hashers::RollingPolynomial;
+    use crate::hashers::{RollingHash, RollingPolynomial, RollingPolynomialMod61};
 
     #[test]
     // Sanity check to make sure the rolling poly hash works as I expect. That is, removing the
@@ -79,9 +197,24 @@ mod tests {
     fn test_rolling_polynomial() {
         let s = "This is a test string. - Normal Person";
         // Testing with window size of 5
-        let mut hash = RollingPolynomial::from_buffer(s[0..5].as_bytes());
-        hash.remove(5, s[0..1].as_bytes()[0]);
-        hash.update(s[5..6].as_bytes()[0]);
-        assert_eq!(hash.hash(), RollingPolynomial::from_buffer_with_salt(s[1..6].as_bytes(), hash.salt).hash());
+        let mut hash = RollingPolynomial::from_buffer(&s.as_bytes()[0..5]);
+        hash.remove(5, s.as_bytes()[0]);
+        hash.update(s.as_bytes()[5]);
+        assert_eq!(hash.hash(), RollingPolynomial::from_buffer_with_salt(&s.as_bytes()[1..6], hash.salt).hash());
+    }
+
+    #[test]
+    // Same as above for the hash modulo 2^61 - 1, with a salt large enough that the products wrap.
+    fn test_rolling_polynomial_mod61() {
+        let s = "This is a test string. - Normal Person";
+        let salt = (1 << 61) - 5;
+        let mut hash = RollingPolynomialMod61::from_buffer_with_salt(&s.as_bytes()[0..5], salt);
+        for i in 0..s.len() - 5 {
+            hash.remove(5, s.as_bytes()[i]);
+            hash.update(s.as_bytes()[i + 5]);
+            assert_eq!(hash.hash(), RollingPolynomialMod61::from_buffer_with_salt(&s.as_bytes()[i + 1..i + 6], salt).hash());
+        }
+        assert!(hash.hash() < (1 << 61) - 1);
+        assert!((2..(1 << 61) - 1).contains(&RollingPolynomialMod61::random_salt()));
     }
 }
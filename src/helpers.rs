@@ -3,7 +3,7 @@ use itertools::Itertools;
 /// Strips out newlines and carriage returns (\n and \r) and strips spaces down to a single space
 /// character. This was advised in the assignment guidelines to match the instructor's results.
 pub fn preprocess_string(string: &str) -> String {
-    if string.len() == 0 {
+    if string.is_empty() {
         return String::new();
     }
     let single_stripped = string.bytes().filter(|byte| {
@@ -31,6 +31,7 @@ pub fn preprocess_string(string: &str) -> String {
 }
 
 
+#[cfg(test)]
 mod tests {
     use crate::helpers::preprocess_string;
 
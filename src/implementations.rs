@@ -1,22 +1,101 @@
-use std::collections::{HashSet, VecDeque};
-use std::hash::Hasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::ops::Range;
 use std::str::CharIndices;
-use rustc_hash::{FxHasher, FxHashSet};
+use rustc_hash::{FxHasher, FxHashMap};
 use hashbrown::raw::RawTable;
 use adler32::RollingAdler32;
-use crate::hashers::RollingPolynomial;
+use crate::bloom::{BlockedBloomFilter, BloomFilterConfig};
+use crate::dna::dna_substring;
+use crate::hashers::{RollingHash, RollingPolynomial, RollingPolynomialMod61};
+use crate::suffix_automaton::SuffixAutomaton;
+
+/// A common substring found in two strings, along with where it occurs in each of them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match<'a> {
+    /// The common substring.
+    pub text: &'a str,
+    /// Byte range of the substring in s1.
+    pub s1_range: Range<usize>,
+    /// Byte range of the substring in s2.
+    pub s2_range: Range<usize>,
+    /// Index (in characters) at which the substring starts in s1.
+    pub s1_char_index: usize,
+    /// Index (in characters) at which the substring starts in s2.
+    pub s2_char_index: usize,
+}
+
+impl<'a> Match<'a> {
+    /// Builds a match for `text` given the (byte offset, char index) at which it starts in s1 and
+    /// s2.
+    pub(crate) fn new(text: &'a str, s1_start: (usize, usize), s2_start: (usize, usize)) -> Self {
+        Match {
+            text,
+            s1_range: s1_start.0..s1_start.0 + text.len(),
+            s2_range: s2_start.0..s2_start.0 + text.len(),
+            s1_char_index: s1_start.1,
+            s2_char_index: s2_start.1,
+        }
+    }
+
+    /// The trivial match of the empty string at the start of both strings.
+    pub(crate) fn empty() -> Self {
+        Match::new("", (0, 0), (0, 0))
+    }
+
+    /// Swaps which of the strings the positions refer to. The implementations that put the
+    /// shorter string in the hash table use this to report positions in terms of the original s1
+    /// and s2.
+    pub(crate) fn swap(self) -> Self {
+        Match {
+            text: self.text,
+            s1_range: self.s2_range,
+            s2_range: self.s1_range,
+            s1_char_index: self.s2_char_index,
+            s2_char_index: self.s1_char_index,
+        }
+    }
+
+    /// Returns the length of the common substring in characters.
+    pub fn char_len(&self) -> usize {
+        self.text.chars().count()
+    }
+}
+
+/// Returns the byte offset at which `sub` starts in `source`. `sub` must be a slice of `source`, like
+/// the substrings returned by the `build_*_substring()` functions.
+pub(crate) fn offset_in(source: &str, sub: &str) -> usize {
+    sub.as_ptr() as usize - source.as_ptr() as usize
+}
+
+/// Ensures that a single closure type across uses of this which, in turn prevents multiple
+/// instances of any functions like RawTable::reserve from being generated. Taken from hashbrown.
+#[inline]
+pub(crate) fn equivalent_key<Q, K, V>(k: &Q) -> impl Fn(&(K, V)) -> bool + '_
+    where
+        K: core::borrow::Borrow<Q>,
+        Q: ?Sized + Eq,
+{
+    move |x| k.eq(x.0.borrow())
+}
 
 /// Naive implementation of substring search. Sticks all k-length substrings of first in a hashmap,
 /// then checks all the k-length substrings in s2 to see if any are already in the hashmap. Runs
 /// in ~O(n) time (n-k+1 insertions for substrings in s1 (where n is the length of s1), up to
 /// n-k+1 queries for substrings in s2 (where n is the length of s2)).
-#[deprecated = "The implementations shouldn't be called directly. Call substring() instead."]
-pub fn _naive_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<&'a str> {
+#[deprecated = "The implementations shouldn't be called directly. Call substring() or use SubstringSearch instead."]
+pub fn _naive_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
     // Trivial to have matching substrings of length 0
     if k == 0 {
         // In this case we opt to return the empty string. Another implementation could return None
         // instead.
-        return Some("");
+        return Some(Match::empty());
+    }
+
+    // When both strings are ASCII, every character is a single byte, so the windows can be sliced
+    // from the strings directly instead of going through the character indices.
+    if s1.is_ascii() && s2.is_ascii() {
+        return ascii_substring(s1, s2, k, HashMap::new());
     }
 
     // Since strings are essentially lists of UTF-8 bytes in Rust, we instead want to iterate over
@@ -30,14 +109,15 @@ pub fn _naive_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<&'a st
         return None;
     }
 
-    let mut substrings = HashSet::new();
+    let mut substrings = HashMap::new();
     for i in 0..(cs1.len()-k+1) {
         let start = cis1[i];
         // Normally we want to read the bytes up until the start of the next character, but when
         // we've reached past the end of the string, it suffices to just read the rest of the string.
         let end = *cis1.get(i+k).unwrap_or(&s1.len());
         let sub = &s1[start..end];
-        substrings.insert(sub);
+        // We only keep track of where a substring first occurs in s1.
+        substrings.entry(sub).or_insert((start, i));
     }
 
     for i in 0..(cs2.len()-k+1) {
@@ -46,14 +126,14 @@ pub fn _naive_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<&'a st
         // we've reached past the end of the string, it suffices to just read the rest of the string.
         let end = *cis2.get(i+k).unwrap_or(&s2.len());
         let sub = &s2[start..end];
-        if substrings.contains(sub) {
+        if let Some(&s1_start) = substrings.get(sub) {
             // Substring found in both s1 and s2, can return early.
-            return Some(sub);
+            return Some(Match::new(sub, s1_start, (start, i)));
         }
     }
 
     // No substring of length k in s2 is also in s1.
-    return None;
+    None
 }
 
 
@@ -62,13 +142,19 @@ pub fn _naive_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<&'a st
 /// in ~O(n) time (n-k+1 insertions for substrings in s1 (where n is the length of s1), up to
 /// n-k+1 queries for substrings in s2 (where n is the length of s2)). This function pre-reserves
 /// the needed size of the hash table up front so rehashing is not needed.
-#[deprecated = "The implementations shouldn't be called directly. Call substring() instead."]
-pub fn _naive_prereserve_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<&'a str> {
+#[deprecated = "The implementations shouldn't be called directly. Call substring() or use SubstringSearch instead."]
+pub fn _naive_prereserve_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
     // Trivial to have matching substrings of length 0
     if k == 0 {
         // In this case we opt to return the empty string. Another implementation could return None
         // instead.
-        return Some("");
+        return Some(Match::empty());
+    }
+
+    // When both strings are ASCII, every character is a single byte, so the windows can be sliced
+    // from the strings directly instead of going through the character indices.
+    if s1.is_ascii() && s2.is_ascii() {
+        return ascii_substring(s1, s2, k, HashMap::with_capacity(s1.len()));
     }
 
     // Since strings are essentially lists of UTF-8 bytes in Rust, we instead want to iterate over
@@ -84,14 +170,15 @@ pub fn _naive_prereserve_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Op
 
     // Note: with_capacity() guarantees that the hash map can hold at least `capacity` elements
     // without reallocating.
-    let mut substrings = HashSet::with_capacity(cs1.len());
+    let mut substrings = HashMap::with_capacity(cs1.len());
     for i in 0..(cs1.len()-k+1) {
         let start = cis1[i];
         // Normally we want to read the bytes up until the start of the next character, but when
         // we've reached past the end of the string, it suffices to just read the rest of the string.
         let end = *cis1.get(i+k).unwrap_or(&s1.len());
         let sub = &s1[start..end];
-        substrings.insert(sub);
+        // We only keep track of where a substring first occurs in s1.
+        substrings.entry(sub).or_insert((start, i));
     }
 
     for i in 0..(cs2.len()-k+1) {
@@ -100,14 +187,14 @@ pub fn _naive_prereserve_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Op
         // we've reached past the end of the string, it suffices to just read the rest of the string.
         let end = *cis2.get(i+k).unwrap_or(&s2.len());
         let sub = &s2[start..end];
-        if substrings.contains(sub) {
+        if let Some(&s1_start) = substrings.get(sub) {
             // Substring found in both s1 and s2, can return early.
-            return Some(sub);
+            return Some(Match::new(sub, s1_start, (start, i)));
         }
     }
 
     // No substring of length k in s2 is also in s1.
-    return None;
+    None
 }
 
 /// Naive implementation of substring search. Sticks all k-length substrings of first in a hashmap,
@@ -116,13 +203,19 @@ pub fn _naive_prereserve_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Op
 /// n-k+1 queries for substrings in s2 (where n is the length of s2)). This function pre-reserves
 /// the needed size of the hash table up front so rehashing is not needed. It also uses the
 /// char_indices() iterator directly instead of copying it to a vec for better performance.
-#[deprecated = "The implementations shouldn't be called directly. Call substring() instead."]
-pub fn _naive_prereserve_iter_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<&'a str> {
+#[deprecated = "The implementations shouldn't be called directly. Call substring() or use SubstringSearch instead."]
+pub fn _naive_prereserve_iter_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
     // Trivial to have matching substrings of length 0
     if k == 0 {
         // In this case we opt to return the empty string. Another implementation could return None
         // instead.
-        return Some("");
+        return Some(Match::empty());
+    }
+
+    // When both strings are ASCII, every character is a single byte, so the windows can be sliced
+    // from the strings directly instead of going through the character indices.
+    if s1.is_ascii() && s2.is_ascii() {
+        return ascii_substring(s1, s2, k, HashMap::with_capacity(s1.len()));
     }
 
     // Since strings are essentially lists of UTF-8 bytes in Rust, we instead want to iterate over
@@ -141,7 +234,7 @@ pub fn _naive_prereserve_iter_substring<'a>(s1: &'a str, s2: &'a str, k: usize)
 
     // Note: with_capacity() guarantees that the hash map can hold at least `capacity` elements
     // without reallocating.
-    let mut substrings = HashSet::with_capacity(cs1_len);
+    let mut substrings = HashMap::with_capacity(cs1_len);
     // We use a Deque so we can quickly slide a window along the indices (using pop_front() and
     // push_back()).
     let mut sub_cs1_ind: VecDeque<usize> = VecDeque::with_capacity(k+1);
@@ -155,37 +248,37 @@ pub fn _naive_prereserve_iter_substring<'a>(s1: &'a str, s2: &'a str, k: usize)
     }
 
     // Helper function to fetch the next substring using a stateful sliding window (sub_indices) of
-    // character indices.
-    fn next_substring<'b>(cs: &mut CharIndices, sub_indices: &mut VecDeque<usize>, source: &'b str) -> &'b str {
+    // character indices. Also returns the byte offset at which the substring starts.
+    fn next_substring<'b>(cs: &mut CharIndices, sub_indices: &mut VecDeque<usize>, source: &'b str) -> (usize, &'b str) {
         // Normally we want to read the bytes up until the start of the next character, but when
         // we've reached past the end of the string, it suffices to just read the rest of the string.
         let (i, _) = cs.next().unwrap_or((source.len(), 'a'));
         sub_indices.push_back(i);
         let start = sub_indices.pop_front().unwrap();
         let end = *sub_indices.back().unwrap();
-        let sub = &source[start..end];
-        sub
+        (start, &source[start..end])
     }
 
-    for _ in k..cs1_len+1 {
-        let sub = next_substring(&mut cs1, &mut sub_cs1_ind, s1);
-        substrings.insert(sub);
+    for i in 0..(cs1_len-k+1) {
+        let (start, sub) = next_substring(&mut cs1, &mut sub_cs1_ind, s1);
+        // We only keep track of where a substring first occurs in s1.
+        substrings.entry(sub).or_insert((start, i));
     }
     // Sanity check to make sure we've read all the characters
     assert!(cs1.next().is_none());
 
-    for _ in k..cs2_len+1 {
-        let sub = next_substring(&mut cs2, &mut sub_cs2_ind, s2);
-        if substrings.contains(sub) {
+    for i in 0..(cs2_len-k+1) {
+        let (start, sub) = next_substring(&mut cs2, &mut sub_cs2_ind, s2);
+        if let Some(&s1_start) = substrings.get(sub) {
             // Substring found in both s1 and s2, can return early.
-            return Some(sub);
+            return Some(Match::new(sub, s1_start, (start, i)));
         }
     }
     // Sanity check to make sure we've read all the characters
     assert!(cs2.next().is_none());
 
     // No substring of length k in s2 is also in s1.
-    return None;
+    None
 }
 
 /// Naive implementation of substring search. Sticks all k-length substrings of first in a hashmap,
@@ -196,13 +289,21 @@ pub fn _naive_prereserve_iter_substring<'a>(s1: &'a str, s2: &'a str, k: usize)
 /// char_indices() iterator directly instead of copying it to a vec for better performance. This
 /// function also uses the firefox hashing algorithm which is faster than Rust's default SIP
 /// hashing algorithm at the cost of reduced resilience against an adversarial user.
-#[deprecated = "The implementations shouldn't be called directly. Call substring() instead."]
-pub fn _naive_prereserve_iter_fx_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<&'a str> {
+#[deprecated = "The implementations shouldn't be called directly. Call substring() or use SubstringSearch instead."]
+pub fn _naive_prereserve_iter_fx_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
     // Trivial to have matching substrings of length 0
     if k == 0 {
         // In this case we opt to return the empty string. Another implementation could return None
         // instead.
-        return Some("");
+        return Some(Match::empty());
+    }
+
+    // When both strings are ASCII, every character is a single byte, so the windows can be sliced
+    // from the strings directly instead of going through the character indices.
+    if s1.is_ascii() && s2.is_ascii() {
+        let mut substrings = FxHashMap::default();
+        substrings.reserve(s1.len());
+        return ascii_substring(s1, s2, k, substrings);
     }
 
     // Since strings are essentially lists of UTF-8 bytes in Rust, we instead want to iterate over
@@ -221,7 +322,7 @@ pub fn _naive_prereserve_iter_fx_substring<'a>(s1: &'a str, s2: &'a str, k: usiz
 
     // Note: we reserve space to guarantee that the hash map can hold at least `capacity` elements
     // without reallocating.
-    let mut substrings = FxHashSet::default();
+    let mut substrings = FxHashMap::default();
     substrings.reserve(cs1_len);
     // We use a Deque so we can quickly slide a window along the indices (using pop_front() and
     // push_back()).
@@ -236,37 +337,64 @@ pub fn _naive_prereserve_iter_fx_substring<'a>(s1: &'a str, s2: &'a str, k: usiz
     }
 
     // Helper function to fetch the next substring using a stateful sliding window (sub_indices) of
-    // character indices.
-    fn next_substring<'b>(cs: &mut CharIndices, sub_indices: &mut VecDeque<usize>, source: &'b str) -> &'b str {
+    // character indices. Also returns the byte offset at which the substring starts.
+    fn next_substring<'b>(cs: &mut CharIndices, sub_indices: &mut VecDeque<usize>, source: &'b str) -> (usize, &'b str) {
         // Normally we want to read the bytes up until the start of the next character, but when
         // we've reached past the end of the string, it suffices to just read the rest of the string.
         let (i, _) = cs.next().unwrap_or((source.len(), 'a'));
         sub_indices.push_back(i);
         let start = sub_indices.pop_front().unwrap();
         let end = *sub_indices.back().unwrap();
-        let sub = &source[start..end];
-        sub
+        (start, &source[start..end])
     }
 
-    for _ in k..cs1_len+1 {
-        let sub = next_substring(&mut cs1, &mut sub_cs1_ind, s1);
-        substrings.insert(sub);
+    for i in 0..(cs1_len-k+1) {
+        let (start, sub) = next_substring(&mut cs1, &mut sub_cs1_ind, s1);
+        // We only keep track of where a substring first occurs in s1.
+        substrings.entry(sub).or_insert((start, i));
     }
     // Sanity check to make sure we've read all the characters
     assert!(cs1.next().is_none());
 
-    for _ in k..cs2_len+1 {
-        let sub = next_substring(&mut cs2, &mut sub_cs2_ind, s2);
-        if substrings.contains(sub) {
+    for i in 0..(cs2_len-k+1) {
+        let (start, sub) = next_substring(&mut cs2, &mut sub_cs2_ind, s2);
+        if let Some(&s1_start) = substrings.get(sub) {
             // Substring found in both s1 and s2, can return early.
-            return Some(sub);
+            return Some(Match::new(sub, s1_start, (start, i)));
         }
     }
     // Sanity check to make sure we've read all the characters
     assert!(cs2.next().is_none());
 
     // No substring of length k in s2 is also in s1.
-    return None;
+    None
+}
+
+// Shared by the naive implementations when both strings are ASCII. Sticks all k-length substrings
+// of s1 in `substrings` (the empty hash table the implementation would otherwise use), then checks
+// the k-length substrings of s2 against it, slicing the windows directly from the bytes.
+fn ascii_substring<'a, S: BuildHasher>(s1: &'a str, s2: &'a str, k: usize, mut substrings: HashMap<&'a str, (usize, usize), S>) -> Option<Match<'a>> {
+    // Impossible to have a substring longer than the original strings.
+    if s1.len() < k || s2.len() < k {
+        return None;
+    }
+
+    for i in 0..(s1.len()-k+1) {
+        // We only keep track of where a substring first occurs in s1. Char indices and byte
+        // offsets are the same.
+        substrings.entry(&s1[i..i+k]).or_insert((i, i));
+    }
+
+    for i in 0..(s2.len()-k+1) {
+        let sub = &s2[i..i+k];
+        if let Some(&s1_start) = substrings.get(sub) {
+            // Substring found in both s1 and s2, can return early.
+            return Some(Match::new(sub, s1_start, (i, i)));
+        }
+    }
+
+    // No substring of length k in s2 is also in s1.
+    None
 }
 
 /// Naive implementation of substring search. Sticks all k-length substrings of the shortest string
@@ -278,87 +406,327 @@ pub fn _naive_prereserve_iter_fx_substring<'a>(s1: &'a str, s2: &'a str, k: usiz
 /// directly instead of copying it to a vec for better performance. This function also uses the
 /// firefox hashing algorithm which is faster than Rust's default SIP hashing algorithm at the cost
 /// of reduced resilience against an adversarial user.
-#[deprecated = "The implementations shouldn't be called directly. Call substring() instead."]
-pub fn _naive_prereserve_iter_fx_shorter_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<&'a str> {
+#[deprecated = "The implementations shouldn't be called directly. Call substring() or use SubstringSearch instead."]
+pub fn _naive_prereserve_iter_fx_shorter_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
     // Trivial to have matching substrings of length 0
     if k == 0 {
         // In this case we opt to return the empty string. Another implementation could return None
         // instead.
-        return Some("");
+        return Some(Match::empty());
     }
 
-    // Since strings are essentially lists of UTF-8 bytes in Rust, we instead want to iterate over
-    // the UTF-8 characters (unicode scalar values). We also keep track of the original indices in
-    // s1 and s2 so we can more efficiently get substrings (without having to create new strings).
-    // We use the iterators directly instead of extracting to a vec for better performance.
-    let cs1_len = s1.chars().count();
-    let cs2_len = s2.chars().count();
+    // The windows slide along the character indices of the strings (or directly along their bytes
+    // when they're ASCII).
+    fx_shorter_window(s1, s2, k).map(|(sub, s1_start, s2_start)| Match::new(sub, s1_start, s2_start))
+}
 
-    // Impossible to have a substring longer than the original strings.
-    if cs1_len < k || cs2_len < k {
-        return None;
+/// Iterator over the k-length substrings of a string. Each item is a tuple of the char index and
+/// byte offset at which the substring starts, along with the substring itself. Uses the same
+/// sliding window of character indices as the implementations above, except for ASCII strings
+/// where every character is a single byte and the substrings can be sliced directly.
+pub(crate) struct KGrams<'a> {
+    source: &'a str,
+    cs: CharIndices<'a>,
+    // Byte offsets of the characters in the current window. Unused for ASCII strings.
+    window: VecDeque<usize>,
+    k: usize,
+    ascii: bool,
+    char_index: usize,
+    done: bool,
+}
+
+impl<'a> KGrams<'a> {
+    pub(crate) fn new(source: &'a str, k: usize) -> Self {
+        let ascii = source.is_ascii();
+        let mut cs = source.char_indices();
+        let mut window = VecDeque::new();
+        if !ascii {
+            window.reserve(k+1);
+            // Pre-loads the indices for the first substring
+            for (i, _) in cs.by_ref().take(k) {
+                window.push_back(i);
+            }
+        }
+        // There are no k-length substrings if the string has fewer than k characters. We also
+        // don't return empty substrings, callers are expected to handle k = 0 themselves.
+        let n_loaded = if ascii { source.len().min(k) } else { window.len() };
+        let done = k == 0 || n_loaded < k;
+        KGrams {
+            source,
+            cs,
+            window,
+            k,
+            ascii,
+            char_index: 0,
+            done,
+        }
     }
+}
 
-    // Choose shorter string to be the one we store in the hash table
-    let (shorter, longer) = if cs1_len <= cs2_len {(s1, s2)} else {(s2, s1)};
-    let (cs_short_len, cs_long_len) = if cs1_len <= cs2_len {(cs1_len, cs2_len)} else {(cs2_len, cs1_len)};
+impl<'a> Iterator for KGrams<'a> {
+    type Item = (usize, usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if self.ascii {
+            // Char indices and byte offsets are the same, so there's no window to keep track of
+            let start = self.char_index;
+            let end = start + self.k;
+            self.done = end == self.source.len();
+            self.char_index += 1;
+            return Some((start, start, &self.source[start..end]));
+        }
+        // Normally we want to read the bytes up until the start of the next character, but when
+        // we've reached past the end of the string, it suffices to just read the rest of the string.
+        let end = match self.cs.next() {
+            Some((i, _)) => i,
+            None => {
+                self.done = true;
+                self.source.len()
+            }
+        };
+        self.window.push_back(end);
+        let start = self.window.pop_front().unwrap();
+        let item = (self.char_index, start, &self.source[start..end]);
+        self.char_index += 1;
+        Some(item)
+    }
+}
+
+/// A sequence whose k-length windows can be stuck in a hash table: strings (whose windows are k
+/// characters long) and slices of any hashable items (like bytes or word IDs).
+pub(crate) trait WindowSource<'a>: Copy {
+    type Window: Hash + Eq + Copy;
+    /// Iterator over the (index, offset, window) of every k-length window, where the offset is the
+    /// byte offset for strings and the same as the index for slices.
+    type Windows: Iterator<Item = (usize, usize, Self::Window)>;
+
+    /// Returns the number of items (characters for strings) in the sequence.
+    fn item_count(self) -> usize;
+
+    /// Returns the k-length windows of the sequence, or nothing if k is 0.
+    fn kgrams(self, k: usize) -> Self::Windows;
+}
+
+impl<'a> WindowSource<'a> for &'a str {
+    type Window = &'a str;
+    type Windows = KGrams<'a>;
 
-    let mut cs_short = shorter.char_indices();
-    let mut cs_long = longer.char_indices();
+    fn item_count(self) -> usize {
+        self.chars().count()
+    }
+
+    fn kgrams(self, k: usize) -> KGrams<'a> {
+        KGrams::new(self, k)
+    }
+}
+
+impl<'a, T: Hash + Eq> WindowSource<'a> for &'a [T] {
+    type Window = &'a [T];
+    type Windows = SliceKGrams<'a, T>;
+
+    fn item_count(self) -> usize {
+        self.len()
+    }
+
+    fn kgrams(self, k: usize) -> SliceKGrams<'a, T> {
+        // windows() doesn't accept a size of 0, so an empty slice stands in for it.
+        let windows = if k == 0 { self[..0].windows(1) } else { self.windows(k) };
+        SliceKGrams { windows: windows.enumerate() }
+    }
+}
+
+/// Iterator over the k-length windows of a slice, see `WindowSource`.
+pub(crate) struct SliceKGrams<'a, T> {
+    windows: std::iter::Enumerate<std::slice::Windows<'a, T>>,
+}
+
+impl<'a, T> Iterator for SliceKGrams<'a, T> {
+    type Item = (usize, usize, &'a [T]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.windows.next().map(|(i, window)| (i, i, window))
+    }
+}
 
+/// Builds the hash table of all the k-length windows of `source` (which has `n_items` items) used
+/// by `_naive_prereserve_iter_fx_shorter_substring()`. The table is pre-reserved and uses the
+/// firefox hashing algorithm. Each window maps to the (offset, index) of its first occurrence in
+/// `source`.
+pub(crate) fn build_fx_table<'a, S: WindowSource<'a>>(source: S, n_items: usize, k: usize) -> FxHashMap<S::Window, (usize, usize)> {
     // Note: we reserve space to guarantee that the hash map can hold at least `capacity` elements
     // without reallocating.
-    let mut substrings = FxHashSet::default();
-    substrings.reserve(cs_short_len);
-    // We use a Deque so we can quickly slide a window along the indices (using pop_front() and
-    // push_back()).
-    let mut sub_cs_short_ind: VecDeque<usize> = VecDeque::with_capacity(k+1);
-    let mut sub_cs_long_ind: VecDeque<usize> = VecDeque::with_capacity(k+1);
-    // Pre-loads the indices for the first substring
-    for _ in 0..k {
-        let (i1, _) = cs_short.next().unwrap();
-        sub_cs_short_ind.push_back(i1);
-        let (i2, _) = cs_long.next().unwrap();
-        sub_cs_long_ind.push_back(i2);
+    let mut substrings = FxHashMap::default();
+    substrings.reserve(n_items);
+    for (i, start, sub) in source.kgrams(k) {
+        substrings.entry(sub).or_insert((start, i));
     }
+    substrings
+}
 
-    // Helper function to fetch the next substring using a stateful sliding window (sub_indices) of
-    // character indices.
-    fn next_substring<'b>(cs: &mut CharIndices, sub_indices: &mut VecDeque<usize>, source: &'b str) -> &'b str {
-        // Normally we want to read the bytes up until the start of the next character, but when
-        // we've reached past the end of the string, it suffices to just read the rest of the string.
-        let (i, _) = cs.next().unwrap_or((source.len(), 'a'));
-        sub_indices.push_back(i);
-        let start = sub_indices.pop_front().unwrap();
-        let end = *sub_indices.back().unwrap();
-        let sub = &source[start..end];
-        sub
+/// Same as `build_fx_table()`, but each window maps to the indices of all its occurrences in
+/// `source` (in order) rather than just the first one.
+pub(crate) fn build_fx_positions_table<'a, S: WindowSource<'a>>(source: S, n_items: usize, k: usize) -> FxHashMap<S::Window, Vec<usize>> {
+    let mut substrings: FxHashMap<S::Window, Vec<usize>> = FxHashMap::default();
+    substrings.reserve(n_items);
+    for (i, _, sub) in source.kgrams(k) {
+        substrings.entry(sub).or_default().push(i);
     }
+    substrings
+}
+
+// A window found in two sequences, along with the (offset, index) of its first occurrence in each.
+pub(crate) type WindowPair<W> = (W, (usize, usize), (usize, usize));
 
-    for _ in k..cs_short_len+1 {
-        let sub = next_substring(&mut cs_short, &mut sub_cs_short_ind, shorter);
-        substrings.insert(sub);
+/// Sticks all the k-length windows of s1 in the table built by `build_fx_table()`, then checks the
+/// windows of s2 in order. Returns the first window of s2 that is also in s1, along with the
+/// (offset, index) of its first occurrence in s1 and in s2. This is how
+/// `_naive_prereserve_iter_fx_substring()` searches strings, and how `Strategy::Fx` searches
+/// slices. k must be at least 1.
+pub(crate) fn fx_window<'a, S: WindowSource<'a>>(s1: S, s2: S, k: usize) -> Option<WindowPair<S::Window>> {
+    fx_window_counted(s1, s1.item_count(), s2, s2.item_count(), k)
+}
+
+/// Same as `fx_window()`, but the shorter sequence is the one stored in the table. Returns the
+/// first window of the longer sequence (or of s2 if both are the same length) that is also in the
+/// shorter one, along with the (offset, index) of its first occurrence in s1 and in s2. This is how
+/// `_naive_prereserve_iter_fx_shorter_substring()` searches strings, and how
+/// `common_subsequence_window()` searches slices. k must be at least 1.
+pub(crate) fn fx_shorter_window<'a, S: WindowSource<'a>>(s1: S, s2: S, k: usize) -> Option<WindowPair<S::Window>> {
+    let n1 = s1.item_count();
+    let n2 = s2.item_count();
+
+    // Choose shorter sequence to be the one we store in the hash table. The positions need to be
+    // swapped back if s2 was the shorter sequence.
+    if n1 <= n2 {
+        fx_window_counted(s1, n1, s2, n2, k)
+    } else {
+        fx_window_counted(s2, n2, s1, n1, k).map(|(window, short_start, long_start)| (window, long_start, short_start))
     }
-    // Sanity check to make sure we've read all the characters
-    assert!(cs_short.next().is_none());
+}
 
-    for _ in k..cs_long_len+1 {
-        let sub = next_substring(&mut cs_long, &mut sub_cs_long_ind, longer);
-        if substrings.contains(sub) {
-            // Substring found in both s1 and s2, can return early.
-            return Some(sub);
+// `fx_window()` for sequences of n1 and n2 items, so callers that already counted them don't have
+// to count them again.
+fn fx_window_counted<'a, S: WindowSource<'a>>(s1: S, n1: usize, s2: S, n2: usize, k: usize) -> Option<WindowPair<S::Window>> {
+    // Impossible to have a window longer than the original sequences.
+    if n1 < k || n2 < k {
+        return None;
+    }
+
+    let windows = build_fx_table(s1, n1, k);
+    for (i, start, window) in s2.kgrams(k) {
+        if let Some(&s1_start) = windows.get(&window) {
+            // Window found in both sequences, can return early.
+            return Some((window, s1_start, (start, i)));
         }
     }
-    // Sanity check to make sure we've read all the characters
-    assert!(cs_long.next().is_none());
 
-    // No substring of length k in s2 is also in s1.
-    return None;
+    // No window of length k in s2 is also in s1.
+    None
+}
+
+/// Lazy iterator over the common substrings of length k of two strings. The k-length substrings
+/// of the shorter string are stored in a hash table up front (just like
+/// `_naive_prereserve_iter_fx_shorter_substring()`), the longer string is then only scanned as
+/// far as needed to return the next common substring. Substrings are returned in the order they
+/// occur in the longer string.
+///
+/// By default a substring is returned for every occurrence in the longer string. Use
+/// `distinct()` to only return each common substring once. Positions in the shorter string always
+/// refer to the first occurrence of the substring.
+pub struct CommonSubstrings<'a> {
+    substrings: FxHashMap<&'a str, (usize, usize)>,
+    longer: KGrams<'a>,
+    distinct: bool,
+    // Whether s2 is the shorter string (in which case match positions need to be swapped back).
+    swapped: bool,
+    // Set when k is 0, in which case we return the empty string once (just like `substring()`).
+    empty: bool,
+}
+
+impl<'a> CommonSubstrings<'a> {
+    pub(crate) fn new(s1: &'a str, s2: &'a str, k: usize) -> Self {
+        let cs1_len = s1.chars().count();
+        let cs2_len = s2.chars().count();
+
+        // Choose shorter string to be the one we store in the hash table
+        let (shorter, longer) = if cs1_len <= cs2_len {(s1, s2)} else {(s2, s1)};
+        let cs_short_len = std::cmp::min(cs1_len, cs2_len);
+
+        CommonSubstrings {
+            // No need to build a table if there can't be any (non-empty) common substrings.
+            substrings: if k == 0 || cs_short_len < k { FxHashMap::default() } else { build_fx_table(shorter, cs_short_len, k) },
+            longer: KGrams::new(longer, k),
+            distinct: false,
+            swapped: cs1_len > cs2_len,
+            empty: k == 0,
+        }
+    }
+
+    /// Only returns each common substring once (at its first occurrence in the longer string)
+    /// instead of once for every occurrence. Should be called before iterating.
+    pub fn distinct(mut self) -> Self {
+        self.distinct = true;
+        self
+    }
+}
+
+impl<'a> Iterator for CommonSubstrings<'a> {
+    type Item = Match<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.empty {
+            self.empty = false;
+            return Some(Match::empty());
+        }
+
+        for (i, start, sub) in self.longer.by_ref() {
+            // Once there are no substrings left in the table, nothing else in the longer string
+            // can match so we can stop early.
+            if self.substrings.is_empty() {
+                return None;
+            }
+            let short_start = if self.distinct {
+                // Each substring is removed from the table the first time it's found so that it
+                // can't be returned again.
+                self.substrings.remove(sub)
+            } else {
+                self.substrings.get(sub).copied()
+            };
+            if let Some(short_start) = short_start {
+                let m = Match::new(sub, short_start, (start, i));
+                return Some(if self.swapped { m.swap() } else { m });
+            }
+        }
+        None
+    }
 }
 
 // Returns a function that, when called, returns the next substring of length k from `source` and
-// its computed hash value.
+// its computed hash value. k must be at least 1.
 pub fn build_rolling_adler_substring<'b>(source: &'b str, k: usize) -> Box<dyn FnMut() -> (&'b str, u64) + 'b> {
+    assert!(k > 0, "rolling hashes need windows of at least 1 character");
+    if source.is_ascii() {
+        // Every character is a single byte, so the window can slide along the bytes directly.
+        let bytes = source.as_bytes();
+        let mut hash = RollingAdler32::from_buffer(&bytes[..k-1]);
+        let mut start = 0;
+        return Box::new(move || {
+            if start > 0 && start <= bytes.len() {
+                // The hash covers the previous window, which is shorter than k past the end
+                hash.remove(k.min(bytes.len() + 1 - start), bytes[start-1]);
+            }
+            if let Some(&b) = bytes.get(start+k-1) {
+                hash.update(b);
+            }
+            let sub = &source[start.min(bytes.len())..(start+k).min(bytes.len())];
+            start += 1;
+            (sub, hash.hash() as u64)
+        });
+    }
+
     let mut cs = source.char_indices();
 
     // We use a Deque so we can quickly slide a window along the indices (using pop_front() and
@@ -373,7 +741,7 @@ pub fn build_rolling_adler_substring<'b>(source: &'b str, k: usize) -> Box<dyn F
         prev_i = i;
     }
 
-    let mut hash = RollingAdler32::from_buffer(source[..prev_i].as_bytes());
+    let mut hash = RollingAdler32::from_buffer(&source.as_bytes()[..prev_i]);
 
     Box::new(move || {
         // Normally we want to read the bytes up until the start of the next character, but when
@@ -392,8 +760,40 @@ pub fn build_rolling_adler_substring<'b>(source: &'b str, k: usize) -> Box<dyn F
 }
 
 // Returns a function that, when called, returns the next substring of length k from `source` and
-// its computed hash value.
+// its computed hash value. k must be at least 1.
 pub fn build_rolling_polynomial_substring<'b>(source: &'b str, k: usize) -> Box<dyn FnMut() -> (&'b str, u64) + 'b> {
+    build_rolling_polynomial_substring_with_salt(source, k, RollingPolynomial::new().salt())
+}
+
+// Same as `build_rolling_polynomial_substring()`, but uses the given salt for the hash. Substrings
+// from different sources only hash to the same value if the same salt is used for both.
+pub fn build_rolling_polynomial_substring_with_salt<'b>(source: &'b str, k: usize, salt: u64) -> Box<dyn FnMut() -> (&'b str, u64) + 'b> {
+    build_rolling_hash_substring(source, k, RollingPolynomial::with_salt(salt))
+}
+
+// Same as `build_rolling_polynomial_substring_with_salt()`, but for any rolling hash. `hash` starts
+// out as the hash of an empty window.
+fn build_rolling_hash_substring<'b, H: RollingHash + 'b>(source: &'b str, k: usize, mut hash: H) -> Box<dyn FnMut() -> (&'b str, u64) + 'b> {
+    assert!(k > 0, "rolling hashes need windows of at least 1 character");
+    if source.is_ascii() {
+        // Every character is a single byte, so the window can slide along the bytes directly.
+        let bytes = source.as_bytes();
+        hash.update_buffer(&bytes[..k-1]);
+        let mut start = 0;
+        return Box::new(move || {
+            if start > 0 && start <= bytes.len() {
+                // The hash covers the previous window, which is shorter than k past the end
+                hash.remove(k.min(bytes.len() + 1 - start) as u32, bytes[start-1]);
+            }
+            if let Some(&b) = bytes.get(start+k-1) {
+                hash.update(b);
+            }
+            let sub = &source[start.min(bytes.len())..(start+k).min(bytes.len())];
+            start += 1;
+            (sub, hash.hash())
+        });
+    }
+
     let mut cs = source.char_indices();
 
     // We use a Deque so we can quickly slide a window along the indices (using pop_front() and
@@ -408,7 +808,7 @@ pub fn build_rolling_polynomial_substring<'b>(source: &'b str, k: usize) -> Box<
         prev_i = i;
     }
 
-    let mut hash = RollingPolynomial::from_buffer(source[..prev_i].as_bytes());
+    hash.update_buffer(&source.as_bytes()[..prev_i]);
 
     Box::new(move || {
         // Normally we want to read the bytes up until the start of the next character, but when
@@ -422,13 +822,24 @@ pub fn build_rolling_polynomial_substring<'b>(source: &'b str, k: usize) -> Box<
         });
         hash.update_buffer(next_char.as_bytes());
         prev_i = i;
-        (&source[old_offset..i], hash.hash() as u64)
+        (&source[old_offset..i], hash.hash())
     })
 }
 
 // Returns a function that, when called, returns the next substring of length k from `source` and
 // its computed hash value.
 pub fn build_fx_substring<'b>(source: &'b str, k: usize) -> Box<dyn FnMut() -> (&'b str, u64) + 'b> {
+    #[inline]
+    fn hash(s: &str) -> u64 {
+        let mut hasher = FxHasher::default();
+        hasher.write(s.as_bytes());
+        hasher.finish()
+    }
+
+    if source.is_ascii() {
+        return build_ascii_substring(source, k, hash);
+    }
+
     let mut cs = source.char_indices();
 
     // We use a Deque so we can quickly slide a window along the indices (using pop_front() and
@@ -443,19 +854,12 @@ pub fn build_fx_substring<'b>(source: &'b str, k: usize) -> Box<dyn FnMut() -> (
         prev_i = i;
     }
 
-    #[inline]
-    fn hash(s: &str) -> u64 {
-        let mut hasher = FxHasher::default();
-        hasher.write(s.as_bytes());
-        hasher.finish()
-    }
-
     Box::new(move || {
         // Normally we want to read the bytes up until the start of the next character, but when
         // we've reached past the end of the string, it suffices to just read the rest of the string.
         let (i, _) = cs.next().unwrap_or((source.len(), 'a'));
         let next_char = &source[prev_i..i];
-        let (old_char, old_offset) = prev_chars.pop_front().unwrap();
+        let (_, old_offset) = prev_chars.pop_front().unwrap();
         prev_chars.push_back((next_char, i));
         prev_i = i;
         let sub = &source[old_offset..i];
@@ -463,9 +867,32 @@ pub fn build_fx_substring<'b>(source: &'b str, k: usize) -> Box<dyn FnMut() -> (
     })
 }
 
+// Same as the `build_*_substring()` functions for ASCII sources, where every character is a single
+// byte. The windows are sliced directly from the bytes instead of keeping track of the character
+// boundaries, and hashed from scratch with `hash`.
+fn build_ascii_substring<'b>(source: &'b str, k: usize, hash: fn(&str) -> u64) -> Box<dyn FnMut() -> (&'b str, u64) + 'b> {
+    let mut start = 0;
+    Box::new(move || {
+        let sub = &source[start.min(source.len())..(start+k).min(source.len())];
+        start += 1;
+        (sub, hash(sub))
+    })
+}
+
 // Returns a function that, when called, returns the next substring of length k from `source` and
 // its computed hash value.
 pub fn build_sip_substring<'b>(source: &'b str, k: usize) -> Box<dyn FnMut() -> (&'b str, u64) + 'b> {
+    #[inline]
+    fn hash(s: &str) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::default();
+        hasher.write(s.as_bytes());
+        hasher.finish()
+    }
+
+    if source.is_ascii() {
+        return build_ascii_substring(source, k, hash);
+    }
+
     let mut cs = source.char_indices();
 
     // We use a Deque so we can quickly slide a window along the indices (using pop_front() and
@@ -480,19 +907,12 @@ pub fn build_sip_substring<'b>(source: &'b str, k: usize) -> Box<dyn FnMut() ->
         prev_i = i;
     }
 
-    #[inline]
-    fn hash(s: &str) -> u64 {
-        let mut hasher = std::collections::hash_map::DefaultHasher::default();
-        hasher.write(s.as_bytes());
-        hasher.finish()
-    }
-
     Box::new(move || {
         // Normally we want to read the bytes up until the start of the next character, but when
         // we've reached past the end of the string, it suffices to just read the rest of the string.
         let (i, _) = cs.next().unwrap_or((source.len(), 'a'));
         let next_char = &source[prev_i..i];
-        let (old_char, old_offset) = prev_chars.pop_front().unwrap();
+        let (_, old_offset) = prev_chars.pop_front().unwrap();
         prev_chars.push_back((next_char, i));
         prev_i = i;
         let sub = &source[old_offset..i];
@@ -508,13 +928,13 @@ pub fn build_sip_substring<'b>(source: &'b str, k: usize) -> Box<dyn FnMut() ->
 /// the hash table up front so rehashing is not needed. It also uses the char_indices() iterator
 /// directly instead of copying it to a vec for better performance. This function also uses the
 /// rolling adler32 hashing algorithm to attempt to improve hashing performance for sliding windows.
-#[deprecated = "The implementations shouldn't be called directly. Call substring() instead."]
-pub fn _naive_prereserve_iter_rolling_adler_shorter_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<&'a str> {
+#[deprecated = "The implementations shouldn't be called directly. Call substring() or use SubstringSearch instead."]
+pub fn _naive_prereserve_iter_rolling_adler_shorter_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
     // Trivial to have matching substrings of length 0
     if k == 0 {
         // In this case we opt to return the empty string. Another implementation could return None
         // instead.
-        return Some("");
+        return Some(Match::empty());
     }
 
     // Since strings are essentially lists of UTF-8 bytes in Rust, we instead want to iterate over
@@ -542,39 +962,31 @@ pub fn _naive_prereserve_iter_rolling_adler_shorter_substring<'a>(s1: &'a str, s
     let mut short_sub_fn = build_rolling_adler_substring(shorter, k);
     let mut long_sub_fn = build_rolling_adler_substring(longer, k);
 
-    /// Ensures that a single closure type across uses of this which, in turn prevents multiple
-    /// instances of any functions like RawTable::reserve from being generated. Taken from hashbrown.
-    #[inline]
-    fn equivalent_key<Q, K, V>(k: &Q) -> impl Fn(&(K, V)) -> bool + '_
-        where
-            K: core::borrow::Borrow<Q>,
-            Q: ?Sized + Eq,
-    {
-        move |x| k.eq(x.0.borrow())
-    }
-
-    for _ in k..cs_short_len+1 {
+    for i in 0..(cs_short_len-k+1) {
         let (sub, hash) = short_sub_fn();
-        // We want to use a hash set, so we only insert into the table if it's not already in there.
+        // We only insert into the table if it's not already in there, which also means we keep
+        // track of where a substring first occurs in the shorter string.
         if substrings.find(hash, equivalent_key(sub)).is_none() {
-            substrings.try_insert_no_grow(hash, (sub, ())).unwrap();
+            substrings.try_insert_no_grow(hash, (sub, (offset_in(shorter, sub), i))).unwrap();
         }
     }
     // Sanity check to make sure we've read all the characters
     assert!(short_sub_fn().0.chars().count() < k);
 
-    for _ in k..cs_long_len+1 {
+    for i in 0..(cs_long_len-k+1) {
         let (sub, hash) = long_sub_fn();
-        if substrings.find(hash, equivalent_key(&sub)).is_some() {
-            // Substring found in both s1 and s2, can return early.
-            return Some(sub);
+        if let Some((_, short_start)) = substrings.get(hash, equivalent_key(&sub)) {
+            // Substring found in both s1 and s2, can return early. The match positions need to be
+            // swapped back if s2 was the shorter string.
+            let m = Match::new(sub, *short_start, (offset_in(longer, sub), i));
+            return Some(if cs1_len <= cs2_len { m } else { m.swap() });
         }
     }
     // Sanity check to make sure we've read all the characters
     assert!(long_sub_fn().0.chars().count() < k);
 
     // No substring of length k in s2 is also in s1.
-    return None;
+    None
 }
 
 /// Naive implementation of substring search. Sticks all k-length substrings of the shortest string
@@ -585,13 +997,13 @@ pub fn _naive_prereserve_iter_rolling_adler_shorter_substring<'a>(s1: &'a str, s
 /// the hash table up front so rehashing is not needed. It also uses the char_indices() iterator
 /// directly instead of copying it to a vec for better performance. This function also uses a
 /// rolling polynomial hash to attempt to improve hashing performance for sliding windows.
-#[deprecated = "The implementations shouldn't be called directly. Call substring() instead."]
-pub fn _naive_prereserve_iter_rolling_poly_shorter_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<&'a str> {
+#[deprecated = "The implementations shouldn't be called directly. Call substring() or use SubstringSearch instead."]
+pub fn _naive_prereserve_iter_rolling_poly_shorter_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
     // Trivial to have matching substrings of length 0
     if k == 0 {
         // In this case we opt to return the empty string. Another implementation could return None
         // instead.
-        return Some("");
+        return Some(Match::empty());
     }
 
     // Since strings are essentially lists of UTF-8 bytes in Rust, we instead want to iterate over
@@ -616,42 +1028,289 @@ pub fn _naive_prereserve_iter_rolling_poly_shorter_substring<'a>(s1: &'a str, s2
     // hash. The standard hash table will just recompute the rolling hash from scratch (I think).
     let mut substrings = RawTable::with_capacity(cs_short_len);
 
-    let mut short_sub_fn = build_rolling_polynomial_substring(shorter, k);
-    let mut long_sub_fn = build_rolling_polynomial_substring(longer, k);
-
-    /// Ensures that a single closure type across uses of this which, in turn prevents multiple
-    /// instances of any functions like RawTable::reserve from being generated. Taken from hashbrown.
-    #[inline]
-    fn equivalent_key<Q, K, V>(k: &Q) -> impl Fn(&(K, V)) -> bool + '_
-        where
-            K: core::borrow::Borrow<Q>,
-            Q: ?Sized + Eq,
-    {
-        move |x| k.eq(x.0.borrow())
-    }
+    // Both strings need to be hashed using the same salt, otherwise the same substrings would end up
+    // with different hashes.
+    let salt = RollingPolynomial::new().salt();
+    let mut short_sub_fn = build_rolling_polynomial_substring_with_salt(shorter, k, salt);
+    let mut long_sub_fn = build_rolling_polynomial_substring_with_salt(longer, k, salt);
 
-    for _ in k..cs_short_len+1 {
+    for i in 0..(cs_short_len-k+1) {
         let (sub, hash) = short_sub_fn();
-        // We want to use a hash set, so we only insert into the table if it's not already in there.
+        // We only insert into the table if it's not already in there, which also means we keep
+        // track of where a substring first occurs in the shorter string.
         if substrings.find(hash, equivalent_key(sub)).is_none() {
-            substrings.try_insert_no_grow(hash, (sub, ())).unwrap();
+            substrings.try_insert_no_grow(hash, (sub, (offset_in(shorter, sub), i))).unwrap();
         }
     }
     // Sanity check to make sure we've read all the characters
     assert!(short_sub_fn().0.chars().count() < k);
 
-    for _ in k..cs_long_len+1 {
+    for i in 0..(cs_long_len-k+1) {
         let (sub, hash) = long_sub_fn();
-        if substrings.find(hash, equivalent_key(&sub)).is_some() {
-            // Substring found in both s1 and s2, can return early.
-            return Some(sub);
+        if let Some((_, short_start)) = substrings.get(hash, equivalent_key(&sub)) {
+            // Substring found in both s1 and s2, can return early. The match positions need to be
+            // swapped back if s2 was the shorter string.
+            let m = Match::new(sub, *short_start, (offset_in(longer, sub), i));
+            return Some(if cs1_len <= cs2_len { m } else { m.swap() });
         }
     }
     // Sanity check to make sure we've read all the characters
     assert!(long_sub_fn().0.chars().count() < k);
 
     // No substring of length k in s2 is also in s1.
-    return None;
+    None
+}
+
+/// Implementation of substring search that stores only 64-bit fingerprints of the shorter string's
+/// substrings instead of the substrings themselves. The fingerprints are rolling polynomial hashes
+/// with a random odd 64-bit salt, and double as the hash used to place them in the table. Each
+/// table entry takes 8 bytes instead of the 32 bytes needed for a substring and its position in
+/// `_naive_prereserve_iter_rolling_poly_shorter_substring()`, which matters for very large inputs
+/// like genomes. Runs in ~O(n) time like the other rolling hash implementations, plus a search
+/// through the shorter string for each candidate (see below).
+///
+/// Since the substrings aren't stored, a fingerprint that is found in the table is only a
+/// candidate. Each candidate is verified by searching for it in the shorter string, so the result
+/// is always exact (and the same as `_naive_prereserve_iter_rolling_poly_shorter_substring()`). A
+/// false positive costs a wasted search through the shorter string (which takes up to O(n·k)
+/// time). For typical (non-adversarial) inputs, each lookup of a substring that isn't in the table
+/// is a false positive with probability about d/2^64 where d is the number of distinct substrings
+/// in the table, so comparing two 1GB strings is expected to run into fewer than 0.1 false
+/// positives. Hashes modulo 2^64 have no such bound for every input though (e.g. Thue-Morse
+/// sequences collide for every odd salt). Use `_fingerprint128_rolling_poly_shorter_substring()`
+/// (or `SubstringSearch::fingerprint_width()`) for a lower false positive rate on any input.
+#[deprecated = "The implementations shouldn't be called directly. Call substring() or use SubstringSearch instead."]
+pub fn _fingerprint_rolling_poly_shorter_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
+    let salt = RollingPolynomial::random_odd_salt();
+    fingerprint_shorter_substring(s1, s2, k, |source| {
+        let mut sub_fn = build_rolling_polynomial_substring_with_salt(source, k, salt);
+        Box::new(move || {
+            let (sub, hash) = sub_fn();
+            (sub, hash, hash)
+        })
+    })
+}
+
+/// Same as `_fingerprint_rolling_poly_shorter_substring()`, but stores 128-bit fingerprints. The
+/// high half is a rolling polynomial hash modulo the prime 2^61 - 1 (a Karp-Rabin hash, see
+/// `RollingPolynomialMod61`) rather than modulo 2^64, so it comes from a different hash family than
+/// the low half and doesn't collide on the same inputs. Each table entry takes 16 bytes. Whatever
+/// the inputs are, each lookup of a substring that isn't in the table is a false positive with
+/// probability at most about d·l/2^61 (where d is the number of distinct substrings in the table
+/// and l is their length in bytes), and about d/2^125 for typical inputs. This makes verifying a false
+/// positive practically never happen, at the cost of hashing every substring twice.
+#[deprecated = "The implementations shouldn't be called directly. Call substring() or use SubstringSearch instead."]
+pub fn _fingerprint128_rolling_poly_shorter_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
+    let (low_salt, high_salt) = (RollingPolynomial::random_odd_salt(), RollingPolynomialMod61::random_salt());
+    fingerprint_shorter_substring(s1, s2, k, |source| {
+        let mut low_fn = build_rolling_polynomial_substring_with_salt(source, k, low_salt);
+        let mut high_fn = build_rolling_hash_substring(source, k, RollingPolynomialMod61::with_salt(high_salt));
+        Box::new(move || {
+            let (sub, low) = low_fn();
+            let (_, high) = high_fn();
+            (sub, low, (high as u128) << 64 | low as u128)
+        })
+    })
+}
+
+// Shared by the fingerprint implementations. `build_sub_fn` returns a function that, when called,
+// returns the next substring of length k from the given source along with the hash used to place it
+// in the table and its fingerprint.
+fn fingerprint_shorter_substring<'a, F: Copy + Eq + std::fmt::Debug>(
+    s1: &'a str,
+    s2: &'a str,
+    k: usize,
+    build_sub_fn: impl Fn(&'a str) -> Box<dyn FnMut() -> (&'a str, u64, F) + 'a>,
+) -> Option<Match<'a>> {
+    // Trivial to have matching substrings of length 0
+    if k == 0 {
+        // In this case we opt to return the empty string. Another implementation could return None
+        // instead.
+        return Some(Match::empty());
+    }
+
+    let cs1_len = s1.chars().count();
+    let cs2_len = s2.chars().count();
+
+    // Impossible to have a substring longer than the original strings.
+    if cs1_len < k || cs2_len < k {
+        return None;
+    }
+
+    // Choose shorter string to be the one we store in the hash table
+    let (shorter, longer) = if cs1_len <= cs2_len {(s1, s2)} else {(s2, s1)};
+    let (cs_short_len, cs_long_len) = if cs1_len <= cs2_len {(cs1_len, cs2_len)} else {(cs2_len, cs1_len)};
+
+    let mut fingerprints: RawTable<F> = RawTable::with_capacity(cs_short_len);
+    let mut short_sub_fn = build_sub_fn(shorter);
+    let mut long_sub_fn = build_sub_fn(longer);
+
+    for _ in 0..(cs_short_len-k+1) {
+        let (_, hash, fingerprint) = short_sub_fn();
+        if fingerprints.find(hash, |f| *f == fingerprint).is_none() {
+            fingerprints.try_insert_no_grow(hash, fingerprint).unwrap();
+        }
+    }
+    // Sanity check to make sure we've read all the characters
+    assert!(short_sub_fn().0.chars().count() < k);
+
+    for i in 0..(cs_long_len-k+1) {
+        let (sub, hash, fingerprint) = long_sub_fn();
+        if fingerprints.find(hash, |f| *f == fingerprint).is_none() {
+            continue;
+        }
+        // Verifies the candidate, which also finds where it first occurs in the shorter string.
+        if let Some(short_offset) = shorter.find(sub) {
+            let short_start = (short_offset, shorter[..short_offset].chars().count());
+            // The match positions need to be swapped back if s2 was the shorter string.
+            let m = Match::new(sub, short_start, (offset_in(longer, sub), i));
+            return Some(if cs1_len <= cs2_len { m } else { m.swap() });
+        }
+    }
+
+    // No substring of length k in s2 is also in s1.
+    None
+}
+
+/// Finds every distinct common substring of length k, like `common_substrings(s1, s2, k).distinct()`:
+/// in the order they first occur in the longer string, at their first occurrence in both strings.
+/// Like the rolling hash implementations, the substrings of the shorter string are stored in a
+/// `RawTable` keyed by their hash, along with whether they were already found in the longer string.
+///
+/// `build_sub_fn` returns a function that, when called, returns the next substring of length k
+/// from the given source and its hash, so each strategy can find all its matches with the same
+/// hasher it uses to find the first one. Both strings need to be hashed the same way. If given a
+/// Bloom filter config, the filter is checked before probing the table like in
+/// `_bloom_prefiltered_shorter_substring()`.
+pub(crate) fn all_shorter_substrings<'a>(
+    s1: &'a str,
+    s2: &'a str,
+    k: usize,
+    bloom: Option<BloomFilterConfig>,
+    build_sub_fn: impl Fn(&'a str) -> Box<dyn FnMut() -> (&'a str, u64) + 'a>,
+) -> Vec<Match<'a>> {
+    // Trivial to have matching substrings of length 0
+    if k == 0 {
+        return vec![Match::empty()];
+    }
+
+    let cs1_len = s1.chars().count();
+    let cs2_len = s2.chars().count();
+
+    // Impossible to have a substring longer than the original strings.
+    if cs1_len < k || cs2_len < k {
+        return Vec::new();
+    }
+
+    // Choose shorter string to be the one we store in the hash table
+    let (shorter, longer) = if cs1_len <= cs2_len {(s1, s2)} else {(s2, s1)};
+    let (cs_short_len, cs_long_len) = if cs1_len <= cs2_len {(cs1_len, cs2_len)} else {(cs2_len, cs1_len)};
+
+    // Each entry holds a substring, the (byte offset, char index) of its first occurrence in the
+    // shorter string, and whether it was already found in the longer string.
+    let mut substrings: RawTable<(&str, (usize, usize), bool)> = RawTable::with_capacity(cs_short_len);
+    let mut filter = bloom.map(|config| BlockedBloomFilter::new(cs_short_len-k+1, config));
+    let mut short_sub_fn = build_sub_fn(shorter);
+    let mut long_sub_fn = build_sub_fn(longer);
+
+    for i in 0..(cs_short_len-k+1) {
+        let (sub, hash) = short_sub_fn();
+        if substrings.find(hash, |(s, _, _)| *s == sub).is_none() {
+            substrings.try_insert_no_grow(hash, (sub, (offset_in(shorter, sub), i), false)).unwrap();
+            if let Some(filter) = &mut filter {
+                filter.insert(hash);
+            }
+        }
+    }
+
+    let mut matches = Vec::new();
+    for i in 0..(cs_long_len-k+1) {
+        let (sub, hash) = long_sub_fn();
+        if filter.as_ref().is_some_and(|filter| !filter.may_contain(hash)) {
+            continue;
+        }
+        if let Some((_, short_start, found)) = substrings.get_mut(hash, |(s, _, _)| *s == sub) {
+            if !*found {
+                *found = true;
+                // The match positions need to be swapped back if s2 was the shorter string.
+                let m = Match::new(sub, *short_start, (offset_in(longer, sub), i));
+                matches.push(if cs1_len <= cs2_len { m } else { m.swap() });
+            }
+        }
+    }
+    matches
+}
+
+/// Implementation of substring search that checks a Bloom filter before probing the hash table.
+/// Like the other rolling hash implementations, it stores the substrings of the shorter string in
+/// a `RawTable` keyed by their hash, but also adds the hashes to a `BlockedBloomFilter` sized with
+/// the given config. Most substrings of the longer string usually aren't in the table, and the
+/// filter rejects most of them with a single cache line access instead of a full table probe.
+///
+/// `build_sub_fn` returns a function that, when called, returns the next substring of length k
+/// from the given source and its hash, so any of the `build_*_substring` hashers can be used. Both
+/// strings need to be hashed the same way (e.g. with the same salt for the rolling polynomial
+/// hash). The filter never rejects a substring that is in the table, so the result is the same as
+/// without the filter.
+#[deprecated = "The implementations shouldn't be called directly. Call substring() or use SubstringSearch instead."]
+pub fn _bloom_prefiltered_shorter_substring<'a>(
+    s1: &'a str,
+    s2: &'a str,
+    k: usize,
+    config: BloomFilterConfig,
+    build_sub_fn: impl Fn(&'a str) -> Box<dyn FnMut() -> (&'a str, u64) + 'a>,
+) -> Option<Match<'a>> {
+    // Trivial to have matching substrings of length 0
+    if k == 0 {
+        // In this case we opt to return the empty string. Another implementation could return None
+        // instead.
+        return Some(Match::empty());
+    }
+
+    let cs1_len = s1.chars().count();
+    let cs2_len = s2.chars().count();
+
+    // Impossible to have a substring longer than the original strings.
+    if cs1_len < k || cs2_len < k {
+        return None;
+    }
+
+    // Choose shorter string to be the one we store in the hash table
+    let (shorter, longer) = if cs1_len <= cs2_len {(s1, s2)} else {(s2, s1)};
+    let (cs_short_len, cs_long_len) = if cs1_len <= cs2_len {(cs1_len, cs2_len)} else {(cs2_len, cs1_len)};
+
+    let mut substrings = RawTable::with_capacity(cs_short_len);
+    let mut filter = BlockedBloomFilter::new(cs_short_len-k+1, config);
+    let mut short_sub_fn = build_sub_fn(shorter);
+    let mut long_sub_fn = build_sub_fn(longer);
+
+    for i in 0..(cs_short_len-k+1) {
+        let (sub, hash) = short_sub_fn();
+        // We only insert into the table if it's not already in there, which also means we keep
+        // track of where a substring first occurs in the shorter string.
+        if substrings.find(hash, equivalent_key(sub)).is_none() {
+            substrings.try_insert_no_grow(hash, (sub, (offset_in(shorter, sub), i))).unwrap();
+            filter.insert(hash);
+        }
+    }
+    // Sanity check to make sure we've read all the characters
+    assert!(short_sub_fn().0.chars().count() < k);
+
+    for i in 0..(cs_long_len-k+1) {
+        let (sub, hash) = long_sub_fn();
+        if !filter.may_contain(hash) {
+            continue;
+        }
+        if let Some((_, short_start)) = substrings.get(hash, equivalent_key(&sub)) {
+            // Substring found in both s1 and s2, can return early. The match positions need to be
+            // swapped back if s2 was the shorter string.
+            let m = Match::new(sub, *short_start, (offset_in(longer, sub), i));
+            return Some(if cs1_len <= cs2_len { m } else { m.swap() });
+        }
+    }
+
+    // No substring of length k in s2 is also in s1.
+    None
 }
 
 /// Implementation of substring search that uses two hash tables to store seen substrings. It
@@ -679,13 +1338,19 @@ pub fn _naive_prereserve_iter_rolling_poly_shorter_substring<'a>(s1: &'a str, s2
 /// better performance. This function also uses the firefox hashing algorithm which is faster than
 /// Rust's  default SIP hashing algorithm at the cost of reduced resilience against an adversarial
 /// user.
-#[deprecated = "The implementations shouldn't be called directly. Call substring() instead."]
-pub fn _alternate_prereserve_iter_fx_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<&'a str> {
+#[deprecated = "The implementations shouldn't be called directly. Call substring() or use SubstringSearch instead."]
+pub fn _alternate_prereserve_iter_fx_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
     // Trivial to have matching substrings of length 0
     if k == 0 {
         // In this case we opt to return the empty string. Another implementation could return None
         // instead.
-        return Some("");
+        return Some(Match::empty());
+    }
+
+    // When both strings are ASCII, every character is a single byte, so the windows can be sliced
+    // from the strings directly instead of going through the character indices.
+    if s1.is_ascii() && s2.is_ascii() {
+        return ascii_alternate_substring(s1, s2, k);
     }
 
     // Since strings are essentially lists of UTF-8 bytes in Rust, we instead want to iterate over
@@ -704,9 +1369,9 @@ pub fn _alternate_prereserve_iter_fx_substring<'a>(s1: &'a str, s2: &'a str, k:
 
     // Note: we reserve space to guarantee that the hash map can hold at least `capacity` elements
     // without reallocating.
-    let mut substrings1 = FxHashSet::default();
+    let mut substrings1 = FxHashMap::default();
     substrings1.reserve(cs1_len);
-    let mut substrings2 = FxHashSet::default();
+    let mut substrings2 = FxHashMap::default();
     substrings2.reserve(cs2_len);
 
     // We use a Deque so we can quickly slide a window along the indices (using pop_front() and
@@ -722,46 +1387,47 @@ pub fn _alternate_prereserve_iter_fx_substring<'a>(s1: &'a str, s2: &'a str, k:
     }
 
     // Helper function to fetch the next substring using a stateful sliding window (sub_indices) of
-    // character indices.
-    fn next_substring<'b>(cs: &mut CharIndices, sub_indices: &mut VecDeque<usize>, source: &'b str) -> &'b str {
+    // character indices. Also returns the byte offset at which the substring starts.
+    fn next_substring<'b>(cs: &mut CharIndices, sub_indices: &mut VecDeque<usize>, source: &'b str) -> (usize, &'b str) {
         // Normally we want to read the bytes up until the start of the next character, but when
         // we've reached past the end of the string, it suffices to just read the rest of the string.
         let (i, _) = cs.next().unwrap_or((source.len(), 'a'));
         sub_indices.push_back(i);
         let start = sub_indices.pop_front().unwrap();
         let end = *sub_indices.back().unwrap();
-        let sub = &source[start..end];
-        sub
+        (start, &source[start..end])
     }
 
-    for _ in k..std::cmp::min(cs1_len, cs2_len)+1 {
-        // Insert next substring
-        let sub1 = next_substring(&mut cs1, &mut sub_cs1_ind, s1);
-        substrings1.insert(sub1);
-        let sub2 = next_substring(&mut cs2, &mut sub_cs2_ind, s2);
-        substrings2.insert(sub2);
+    for i in 0..(std::cmp::min(cs1_len, cs2_len)-k+1) {
+        // Insert next substring (keeping track of where it first occurs)
+        let (start1, sub1) = next_substring(&mut cs1, &mut sub_cs1_ind, s1);
+        substrings1.entry(sub1).or_insert((start1, i));
+        let (start2, sub2) = next_substring(&mut cs2, &mut sub_cs2_ind, s2);
+        substrings2.entry(sub2).or_insert((start2, i));
 
         // Then check to see if that substring has been before in the other string. We need to
         // insert before checking to make sure we don't end up in a case where a matching substring
         // is deemed not to be matching because both strings insert it simultaneously after
         // determining that it is not in the other yet, and may never be checked again.
-        if substrings2.contains(sub1) {
+        if let Some(&s2_start) = substrings2.get(sub1) {
             // Substring found in both s1 and s2, can return early.
-            return Some(sub1);
-        } else if substrings1.contains(sub2) {
+            return Some(Match::new(sub1, (start1, i), s2_start));
+        } else if let Some(&s1_start) = substrings1.get(sub2) {
             // Substring found in both s1 and s2, can return early.
-            return Some(sub2);
+            return Some(Match::new(sub2, s1_start, (start2, i)));
         }
     }
 
     // One of the strings has been fully inserted, this means we only need to check substrings of
     // the other to see if their in the fully inserted string.
     let (longer, longer_ind, longer_s, shorter_table) = if cs1_len <= cs2_len {(&mut cs2, &mut sub_cs2_ind, s2, &substrings1)} else {(&mut cs1, &mut sub_cs1_ind, s1, &substrings2)};
-    for _ in std::cmp::min(cs1_len, cs2_len)+1..std::cmp::max(cs1_len, cs2_len)+1 {
-        let sub = next_substring(longer, longer_ind, longer_s);
-        if shorter_table.contains(sub) {
-            // Substring found in both s1 and s2, can return early.
-            return Some(sub);
+    for i in (std::cmp::min(cs1_len, cs2_len)-k+1)..(std::cmp::max(cs1_len, cs2_len)-k+1) {
+        let (start, sub) = next_substring(longer, longer_ind, longer_s);
+        if let Some(&short_start) = shorter_table.get(sub) {
+            // Substring found in both s1 and s2, can return early. The match positions need to be
+            // swapped back if s1 is the longer string.
+            let m = Match::new(sub, short_start, (start, i));
+            return Some(if cs1_len <= cs2_len { m } else { m.swap() });
         }
     }
 
@@ -770,13 +1436,116 @@ pub fn _alternate_prereserve_iter_fx_substring<'a>(s1: &'a str, s2: &'a str, k:
     assert!(cs2.next().is_none());
 
     // No substring of length k in s2 is also in s1.
-    return None;
+    None
+}
+
+// Same as `_alternate_prereserve_iter_fx_substring()` when both strings are ASCII, slicing the
+// windows directly from the bytes (char indices and byte offsets are then the same).
+fn ascii_alternate_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
+    // Impossible to have a substring longer than the original strings.
+    if s1.len() < k || s2.len() < k {
+        return None;
+    }
+
+    let mut substrings1 = FxHashMap::default();
+    substrings1.reserve(s1.len());
+    let mut substrings2 = FxHashMap::default();
+    substrings2.reserve(s2.len());
+
+    for i in 0..(std::cmp::min(s1.len(), s2.len())-k+1) {
+        // Insert before checking, for the same reasons as in the character-based version
+        let (sub1, sub2) = (&s1[i..i+k], &s2[i..i+k]);
+        substrings1.entry(sub1).or_insert((i, i));
+        substrings2.entry(sub2).or_insert((i, i));
+
+        if let Some(&s2_start) = substrings2.get(sub1) {
+            return Some(Match::new(sub1, (i, i), s2_start));
+        } else if let Some(&s1_start) = substrings1.get(sub2) {
+            return Some(Match::new(sub2, s1_start, (i, i)));
+        }
+    }
+
+    // One of the strings has been fully inserted, so only the rest of the other one needs checking.
+    let (longer, shorter_table) = if s1.len() <= s2.len() {(s2, &substrings1)} else {(s1, &substrings2)};
+    for i in (std::cmp::min(s1.len(), s2.len())-k+1)..(longer.len()-k+1) {
+        let sub = &longer[i..i+k];
+        if let Some(&short_start) = shorter_table.get(sub) {
+            // The match positions need to be swapped back if s1 is the longer string.
+            let m = Match::new(sub, short_start, (i, i));
+            return Some(if s1.len() <= s2.len() { m } else { m.swap() });
+        }
+    }
+
+    // No substring of length k in s2 is also in s1.
+    None
+}
+
+/// Implementation of substring search specialised for DNA sequences. Uses `dna_substring()`, which
+/// packs the k-mers of both strings into `u64`s (2 bits per base) instead of hashing substrings,
+/// and finds the same substring as `_naive_prereserve_iter_fx_shorter_substring()`. Falls back to
+/// `_naive_prereserve_iter_fx_shorter_substring()` when the strings contain anything but the bases
+/// A, C, G, T and N (like the headers and newlines of FASTA files, see `fasta_sequence()`) or when k
+/// is larger than `MAX_DNA_K`.
+#[deprecated = "The implementations shouldn't be called directly. Call substring() or use SubstringSearch instead."]
+#[allow(deprecated)]
+pub fn _dna_packed_shorter_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
+    dna_substring(s1, s2, k).unwrap_or_else(|_| _naive_prereserve_iter_fx_shorter_substring(s1, s2, k))
+}
+
+/// Implementation of substring search that uses a suffix automaton instead of a hash table. Builds
+/// the suffix automaton of the shorter string (the smallest automaton that accepts all of its
+/// suffixes), then runs the other string through it while keeping track of the longest suffix read
+/// so far that is also a substring of the shorter string. Runs in ~O(n) time (n steps to build the
+/// automaton for the shorter string (where n is the length of the string), up to n steps to scan
+/// the other string (where n is the length of the string)).
+///
+/// Unlike the hash tables, the automaton doesn't depend on k. Use `SuffixAutomaton` directly to
+/// build it once and query it for several values of k or for the longest common substring.
+#[deprecated = "The implementations shouldn't be called directly. Call substring() or use SubstringSearch instead."]
+pub fn _suffix_automaton_shorter_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
+    // Trivial to have matching substrings of length 0
+    if k == 0 {
+        // In this case we opt to return the empty string. Another implementation could return None
+        // instead.
+        return Some(Match::empty());
+    }
+
+    let cs1_len = s1.chars().count();
+    let cs2_len = s2.chars().count();
+
+    // Impossible to have a substring longer than the original strings.
+    if cs1_len < k || cs2_len < k {
+        return None;
+    }
+
+    // Choose shorter string to be the one we build the automaton for. The match positions need to
+    // be swapped back if s2 was the shorter string.
+    if cs1_len <= cs2_len {
+        SuffixAutomaton::new(s1).find(s2, k)
+    } else {
+        SuffixAutomaton::new(s2).find(s1, k).map(|m| m.swap())
+    }
 }
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use adler32::RollingAdler32;
-    use crate::implementations::build_rolling_adler_substring;
+    use proptest::prelude::*;
+    use crate::implementations::*;
+    use crate::search::{Implementation, Strategy, SubstringFinder};
+
+    // Checks that the positions in the match actually point at the matching substring in s1 and s2.
+    fn match_positions_correct(m: &Match, s1: &str, s2: &str, k: usize) -> bool {
+        let char_offset = |s: &str, char_index: usize| {
+            s.char_indices().nth(char_index).map(|(i, _)| i).unwrap_or(s.len())
+        };
+        m.char_len() == k
+            && &s1[m.s1_range.clone()] == m.text
+            && &s2[m.s2_range.clone()] == m.text
+            && char_offset(s1, m.s1_char_index) == m.s1_range.start
+            && char_offset(s2, m.s2_char_index) == m.s2_range.start
+    }
 
     #[test]
     // Sanity check to make sure the rolling adler hash works as I expect. That is, removing the
@@ -785,10 +1554,10 @@ mod tests {
     fn test_adler32_rolling() {
         let s = "This is a test string. - Normal Person";
         // Testing with window size of 5
-        let mut hash = RollingAdler32::from_buffer(s[0..5].as_bytes());
-        hash.remove(5, s[0..1].as_bytes()[0]);
-        hash.update(s[5..6].as_bytes()[0]);
-        assert_eq!(hash.hash(), RollingAdler32::from_buffer(s[1..6].as_bytes()).hash());
+        let mut hash = RollingAdler32::from_buffer(&s.as_bytes()[0..5]);
+        hash.remove(5, s.as_bytes()[0]);
+        hash.update(s.as_bytes()[5]);
+        assert_eq!(hash.hash(), RollingAdler32::from_buffer(&s.as_bytes()[1..6]).hash());
     }
 
     #[test]
@@ -806,4 +1575,185 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    #[should_panic(expected = "windows of at least 1 character")]
+    fn test_rolling_adler_empty_window() {
+        let _ = build_rolling_adler_substring("abc", 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "windows of at least 1 character")]
+    fn test_rolling_polynomial_empty_window() {
+        let _ = build_rolling_polynomial_substring("abc", 0);
+    }
+
+    #[test]
+    fn test_kgrams_ascii() {
+        let s = "This is a test";
+        let kgrams: Vec<_> = KGrams::new(s, 4).collect();
+        assert_eq!(kgrams.len(), s.len() - 3);
+        assert_eq!(kgrams[0], (0, 0, "This"));
+        assert_eq!(kgrams[10], (10, 10, "test"));
+        assert_eq!(KGrams::new(s, 15).next(), None);
+        assert_eq!(KGrams::new(s, 0).next(), None);
+    }
+
+    #[test]
+    fn test_match_positions() {
+        // Test strings that include multi-byte characters. s1 is the longer string so that the
+        // shorter-first implementations have to swap the positions back.
+        let s1 = "›It costs €10 for this item… or €12 for that one";
+        let s2 = "Pay€10 now";
+        let k = 4;
+
+        for finder in Strategy::ALL {
+            let m = finder.find(s1, s2, k).unwrap();
+            assert_eq!(m.text, "€10 ");
+            assert_eq!(m.s1_range, s1.find("€10 ").unwrap()..s1.find("€10 ").unwrap() + "€10 ".len());
+            assert_eq!(m.s2_range, 3..9);
+            assert_eq!(m.s1_char_index, 10);
+            assert_eq!(m.s2_char_index, 3);
+        }
+    }
+
+    #[test]
+    fn test_empty_match() {
+        for finder in Strategy::ALL {
+            assert_eq!(finder.find("abc", "def", 0), Some(Match::new("", (0, 0), (0, 0))));
+        }
+    }
+
+    #[test]
+    // Makes every fingerprint collide so every substring of the longer string is a candidate. The
+    // verification should still reject the false positives.
+    fn test_fingerprint_false_positives() {
+        let s1 = "This is a test string. - Normal Person";
+        let s2 = "Here be another test string. Yaargh. - Pirate";
+        let colliding = |k| move |source: &'static str| -> Box<dyn FnMut() -> (&'static str, u64, u64)> {
+            let mut sub_fn = build_fx_substring(source, k);
+            Box::new(move || (sub_fn().0, 0, 0))
+        };
+
+        assert_eq!(fingerprint_shorter_substring(s1, s2, 5, colliding(5)), _naive_prereserve_iter_fx_shorter_substring(s1, s2, 5));
+        assert_eq!(fingerprint_shorter_substring(s1, s2, 15, colliding(15)), None);
+    }
+
+    proptest! {
+        #[test]
+        // The fingerprint implementations verify their candidates, so they should return exactly
+        // the same match as the implementation that stores the substrings.
+        fn test_fingerprints_exact(
+            s1 in "[abc€]{0,20}",
+            s2 in "[abc€]{0,20}",
+            k in 0..6usize,
+        ) {
+            let expected = _naive_prereserve_iter_rolling_poly_shorter_substring(&s1, &s2, k);
+            assert_eq!(_fingerprint_rolling_poly_shorter_substring(&s1, &s2, k), expected);
+            assert_eq!(_fingerprint128_rolling_poly_shorter_substring(&s1, &s2, k), expected);
+        }
+
+        #[test]
+        // The filter shouldn't change the result, whichever hasher is used.
+        fn test_bloom_prefiltered_exact(
+            s1 in "[abc€]{0,20}",
+            s2 in "[abc€]{0,20}",
+            k in 0..6usize,
+            bits_per_item in 1..12usize,
+        ) {
+            let config = BloomFilterConfig::new().bits_per_item(bits_per_item);
+            let expected = _naive_prereserve_iter_fx_shorter_substring(&s1, &s2, k);
+            let salt = RollingPolynomial::new().salt();
+            assert_eq!(_bloom_prefiltered_shorter_substring(&s1, &s2, k, config, |s| build_fx_substring(s, k)), expected);
+            assert_eq!(_bloom_prefiltered_shorter_substring(&s1, &s2, k, config, |s| build_sip_substring(s, k)), expected);
+            assert_eq!(_bloom_prefiltered_shorter_substring(&s1, &s2, k, config, |s| build_rolling_adler_substring(s, k)), expected);
+            assert_eq!(_bloom_prefiltered_shorter_substring(&s1, &s2, k, config, |s| build_rolling_polynomial_substring_with_salt(s, k, salt)), expected);
+        }
+
+        #[test]
+        // The ASCII fast paths slide along the bytes directly, which should produce the same
+        // substrings and hashes as sliding along the characters.
+        fn test_ascii_next_substring(s in "[ab ]{1,20}", k in 1..6usize) {
+            let n = s.chars().count();
+            prop_assume!(k <= n);
+            let salt = RollingPolynomial::new().salt();
+            let mut adler_fn = build_rolling_adler_substring(&s, k);
+            let mut poly_fn = build_rolling_polynomial_substring_with_salt(&s, k, salt);
+            let mut fx_fn = build_fx_substring(&s, k);
+            let mut sip_fn = build_sip_substring(&s, k);
+            let kgrams: Vec<_> = KGrams::new(&s, k).collect();
+            assert_eq!(kgrams.len(), n - k + 1);
+            // The extra iteration checks the substrings past the end, which are shorter than k
+            for i in 0..n-k+2 {
+                let expected_sub = s.chars().skip(i).take(k).collect::<String>();
+                let expected_sub = expected_sub.as_str();
+                if let Some(&kgram) = kgrams.get(i) {
+                    assert_eq!(kgram, (i, i, expected_sub));
+                }
+                assert_eq!(adler_fn(), (expected_sub, RollingAdler32::from_buffer(expected_sub.as_bytes()).hash() as u64));
+                assert_eq!(poly_fn(), (expected_sub, RollingPolynomial::from_buffer_with_salt(expected_sub.as_bytes(), salt).hash()));
+                let mut fx = rustc_hash::FxHasher::default();
+                fx.write(expected_sub.as_bytes());
+                assert_eq!(fx_fn(), (expected_sub, fx.finish()));
+                let mut sip = std::collections::hash_map::DefaultHasher::default();
+                sip.write(expected_sub.as_bytes());
+                assert_eq!(sip_fn(), (expected_sub, sip.finish()));
+            }
+        }
+
+        #[test]
+        // The ASCII fast paths shouldn't change which match is found.
+        fn test_ascii_matches_exact(
+            s1 in "[abc]{0,20}",
+            s2 in "[abc]{0,20}",
+            k in 0..6usize,
+        ) {
+            // Adding a different multi-byte character to the end of both strings forces the
+            // character-based windows without changing the match, since the windows that include
+            // them are scanned last and can't match.
+            let (t1, t2) = (format!("{}€", s1), format!("{}…", s2));
+            for finder in Strategy::ALL {
+                assert_eq!(finder.find(&s1, &s2, k), finder.find(&t1, &t2, k));
+            }
+        }
+
+        #[test]
+        // The naive and alternate implementations each have their own ASCII fast path, which
+        // shouldn't change which match they find.
+        fn test_ascii_matches_exact_naive_implementations(
+            s1 in "[abc]{0,20}",
+            s2 in "[abc]{0,20}",
+            k in 0..6usize,
+        ) {
+            let (t1, t2) = (format!("{}€", s1), format!("{}…", s2));
+            let implementations: [(&str, Implementation); 5] = [
+                ("naive_substring", _naive_substring),
+                ("naive_prereserve_substring", _naive_prereserve_substring),
+                ("naive_prereserve_iter_substring", _naive_prereserve_iter_substring),
+                ("naive_prereserve_iter_fx_substring", _naive_prereserve_iter_fx_substring),
+                ("alternate_prereserve_iter_fx_substring", _alternate_prereserve_iter_fx_substring),
+            ];
+            for (name, implementation) in implementations {
+                assert_eq!(implementation(&s1, &s2, k), implementation(&t1, &t2, k), "{}", name);
+            }
+        }
+
+        #[test]
+        // Tests that every implementation finds a match exactly when there is one, and that the
+        // positions it reports are correct.
+        fn test_match_positions_all_implementations(
+            s1 in "[abc€]{0,20}",
+            s2 in "[abc€]{0,20}",
+            k in 1..6usize,
+        ) {
+            let expected = _naive_substring(&s1, &s2, k).is_some();
+            for finder in Strategy::ALL {
+                let r = finder.find(&s1, &s2, k);
+                assert_eq!(r.is_some(), expected);
+                if let Some(m) = r {
+                    assert!(match_positions_correct(&m, &s1, &s2, k));
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,273 @@
+use hashbrown::raw::RawTable;
+use rustc_hash::FxHashMap;
+use crate::hashers::RollingPolynomial;
+use crate::implementations::{build_rolling_polynomial_substring_with_salt, equivalent_key, offset_in, KGrams, Match};
+
+/// The kind of hash table a `KmerIndex` stores its k-length substrings in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexBackend {
+    /// A hash table keyed by the substrings themselves using the firefox hashing algorithm (the same
+    /// table `_naive_prereserve_iter_fx_shorter_substring()` uses).
+    Fx,
+    /// A `RawTable` keyed by a rolling polynomial hash of the substrings (the same table
+    /// `_naive_prereserve_iter_rolling_poly_shorter_substring()` uses).
+    RollingPolynomial,
+}
+
+enum Table<'a> {
+    Fx(FxHashMap<&'a str, (usize, usize)>),
+    // The salt needs to be kept around so that queries hash substrings the same way.
+    RollingPolynomial(RawTable<(&'a str, (usize, usize))>, u64),
+}
+
+/// A prebuilt hash table of all the k-length substrings of a text. Building the table dominates
+/// the running time of a substring search, so when one text is compared against many others it is
+/// much faster to build the table once and query it for each of the other texts.
+///
+/// Matches returned by the index use the indexed text as s1 and the queried text as s2. Positions
+/// in the indexed text always refer to the first occurrence of the substring.
+pub struct KmerIndex<'a> {
+    text: &'a str,
+    k: usize,
+    table: Table<'a>,
+}
+
+impl<'a> KmerIndex<'a> {
+    /// Builds an index of all the k-length substrings of `text` using the `Fx` backend.
+    pub fn build(text: &'a str, k: usize) -> Self {
+        Self::build_with_backend(text, k, IndexBackend::Fx)
+    }
+
+    /// Builds an index of all the k-length substrings of `text` using the given backend.
+    pub fn build_with_backend(text: &'a str, k: usize, backend: IndexBackend) -> Self {
+        let n_chars = text.chars().count();
+        // There are no k-length substrings to index if the text has fewer than k characters. The
+        // rolling hash functions also expect at least k characters.
+        let n_substrings = if k == 0 || n_chars < k { 0 } else { n_chars - k + 1 };
+
+        let table = match backend {
+            IndexBackend::Fx => {
+                // Note: we reserve space to guarantee that the hash map can hold at least
+                // `capacity` elements without reallocating.
+                let mut substrings = FxHashMap::default();
+                substrings.reserve(n_substrings);
+                for (i, start, sub) in KGrams::new(text, k) {
+                    // We only keep track of where a substring first occurs in the text.
+                    substrings.entry(sub).or_insert((start, i));
+                }
+                Table::Fx(substrings)
+            },
+            IndexBackend::RollingPolynomial => {
+                let salt = RollingPolynomial::new().salt();
+                let mut substrings = RawTable::with_capacity(n_substrings);
+                if n_substrings > 0 {
+                    let mut sub_fn = build_rolling_polynomial_substring_with_salt(text, k, salt);
+                    for i in 0..n_substrings {
+                        let (sub, hash) = sub_fn();
+                        // We only insert into the table if it's not already in there, which also
+                        // means we keep track of where a substring first occurs in the text.
+                        if substrings.find(hash, equivalent_key(sub)).is_none() {
+                            substrings.try_insert_no_grow(hash, (sub, (offset_in(text, sub), i))).unwrap();
+                        }
+                    }
+                }
+                Table::RollingPolynomial(substrings, salt)
+            },
+        };
+
+        KmerIndex {
+            text,
+            k,
+            table,
+        }
+    }
+
+    /// Returns the indexed text.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Returns the length (in characters) of the indexed substrings.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the number of distinct k-length substrings in the index.
+    pub fn len(&self) -> usize {
+        match &self.table {
+            Table::Fx(substrings) => substrings.len(),
+            Table::RollingPolynomial(substrings, _) => substrings.len(),
+        }
+    }
+
+    /// Returns true if the index doesn't hold any substrings (the text is shorter than k).
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the first k-length substring in `other` that also occurs in the indexed text, or None
+    /// if there is no such substring.
+    pub fn query<'b>(&self, other: &'b str) -> Option<Match<'b>> where 'a: 'b {
+        self.matches(other).next()
+    }
+
+    /// Returns if `other` has a k-length substring in common with the indexed text.
+    pub fn contains(&self, other: &str) -> bool {
+        self.query(other).is_some()
+    }
+
+    /// Returns a lazy iterator over every k-length substring in `other` that also occurs in the
+    /// indexed text, in the order they occur in `other`.
+    pub fn matches<'i, 'b>(&'i self, other: &'b str) -> IndexMatches<'i, 'a, 'b> where 'a: 'b {
+        let windows = match &self.table {
+            Table::Fx(_) => Windows::Fx(KGrams::new(other, self.k)),
+            Table::RollingPolynomial(_, salt) => {
+                let n_chars = other.chars().count();
+                if self.is_empty() || n_chars < self.k {
+                    Windows::Rolling { sub_fn: None, source: other, char_index: 0, remaining: 0 }
+                } else {
+                    Windows::Rolling {
+                        sub_fn: Some(build_rolling_polynomial_substring_with_salt(other, self.k, *salt)),
+                        source: other,
+                        char_index: 0,
+                        remaining: n_chars - self.k + 1,
+                    }
+                }
+            },
+        };
+        IndexMatches {
+            index: self,
+            windows,
+            empty: self.k == 0,
+        }
+    }
+}
+
+// The k-length substrings of a queried text (along with their char index and byte offset), computed
+// the way the index's backend expects.
+enum Windows<'b> {
+    Fx(KGrams<'b>),
+    Rolling {
+        // None if there are no substrings to check.
+        sub_fn: Option<Box<dyn FnMut() -> (&'b str, u64) + 'b>>,
+        source: &'b str,
+        char_index: usize,
+        remaining: usize,
+    },
+}
+
+/// Lazy iterator over the substrings of a text that occur in a `KmerIndex`. See
+/// `KmerIndex::matches()`.
+pub struct IndexMatches<'i, 'a, 'b> {
+    index: &'i KmerIndex<'a>,
+    windows: Windows<'b>,
+    // Set when k is 0, in which case we return the empty string once (just like `substring()`).
+    empty: bool,
+}
+
+impl<'i, 'a: 'b, 'b> Iterator for IndexMatches<'i, 'a, 'b> {
+    type Item = Match<'b>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.empty {
+            self.empty = false;
+            return Some(Match::empty());
+        }
+
+        match (&mut self.windows, &self.index.table) {
+            (Windows::Fx(kgrams), Table::Fx(substrings)) => {
+                for (i, start, sub) in kgrams {
+                    if let Some(&text_start) = substrings.get(sub) {
+                        return Some(Match::new(sub, text_start, (start, i)));
+                    }
+                }
+            },
+            (Windows::Rolling { sub_fn: Some(sub_fn), source, char_index, remaining }, Table::RollingPolynomial(substrings, _)) => {
+                while *remaining > 0 {
+                    let (sub, hash) = sub_fn();
+                    let i = *char_index;
+                    *char_index += 1;
+                    *remaining -= 1;
+                    if let Some((_, text_start)) = substrings.get(hash, equivalent_key(&sub)) {
+                        return Some(Match::new(sub, *text_start, (offset_in(source, sub), i)));
+                    }
+                }
+            },
+            _ => {},
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use proptest::prelude::*;
+    use crate::implementations::_naive_substring;
+    use crate::index::{IndexBackend, KmerIndex};
+
+    const BACKENDS: [IndexBackend; 2] = [IndexBackend::Fx, IndexBackend::RollingPolynomial];
+
+    #[test]
+    fn test_index_query() {
+        let reference = "This is a test string. - Normal Person";
+        let candidates = [
+            "Here be another test string. Yaargh. - Pirate",
+            "Who lives in a pineapple under the sea? - Patchy",
+            "Test",
+        ];
+        let k = 5;
+
+        for backend in BACKENDS {
+            let index = KmerIndex::build_with_backend(reference, k, backend);
+            assert_eq!(index.k(), k);
+            assert_eq!(index.len(), reference.len() - k + 1);
+
+            let m = index.query(candidates[0]).unwrap();
+            assert_eq!(m.text, " test");
+            assert_eq!(m.s1_range, 9..14);
+            assert_eq!(m.s2_range, 15..20);
+            assert!(index.contains(candidates[0]));
+            assert!(!index.contains(candidates[1]));
+            assert!(!index.contains(candidates[2]));
+        }
+    }
+
+    #[test]
+    fn test_index_matches() {
+        let index = KmerIndex::build("abcabc", 3);
+        let matches: Vec<_> = index.matches("xabcxbca").map(|m| (m.text, m.s1_char_index, m.s2_char_index)).collect();
+        assert_eq!(matches, vec![("abc", 0, 1), ("bca", 1, 5)]);
+    }
+
+    #[test]
+    fn test_index_short_text() {
+        for backend in BACKENDS {
+            let index = KmerIndex::build_with_backend("abc", 5, backend);
+            assert!(index.is_empty());
+            assert_eq!(index.query("abcdefgh"), None);
+            assert_eq!(KmerIndex::build_with_backend("abcdefgh", 5, backend).query("abc"), None);
+        }
+    }
+
+    proptest! {
+        #[test]
+        // Tests behavior against the naive implementation, which also puts the first string in the
+        // hash table and returns the first match in the second string.
+        fn test_index_against_naive(
+            s1 in "[abc€]{0,20}",
+            s2 in "[abc€]{0,20}",
+            k in 0..6usize,
+        ) {
+            let expected = _naive_substring(&s1, &s2, k);
+            for backend in BACKENDS {
+                let index = KmerIndex::build_with_backend(&s1, k, backend);
+                assert_eq!(index.query(&s2), expected.clone());
+                assert_eq!(index.matches(&s2).next(), expected.clone());
+                if k > 0 {
+                    assert!(index.matches(&s2).all(|m| s1.contains(m.text) && &s2[m.s2_range] == m.text));
+                }
+            }
+        }
+    }
+}
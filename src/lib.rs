@@ -1,27 +1,105 @@
+pub mod approximate;
+pub mod bloom;
+pub mod dna;
 pub mod helpers;
 pub mod implementations;
+pub mod index;
+pub mod minhash;
+pub mod minimizer;
+pub mod multi;
+pub mod overlap;
+pub mod regions;
+pub mod search;
+pub mod sequences;
+pub mod suffix_array;
+pub mod suffix_automaton;
+pub mod winnowing;
 mod hashers;
 
+pub use approximate::{approx_common_substring, edit_similar_regions, ApproxMatch};
+pub use bloom::BloomFilterConfig;
+pub use dna::{canonical_dna_substring, dna_substring, fasta_sequence, reverse_complement, CanonicalMatch, DnaError, Strand};
+pub use implementations::Match;
+pub use index::{IndexBackend, KmerIndex};
+pub use minhash::MinHashSketch;
+pub use minimizer::{minimizer_substring, minimizers, Minimizer};
+pub use multi::{common_substring_n, SharedSubstring};
+pub use overlap::{kgram_overlap, KGramOverlap};
+pub use regions::shared_regions;
+pub use search::{FingerprintWidth, MatchOrder, Strategy, SubstringFinder, SubstringSearch};
+pub use sequences::{common_subsequence_window, common_subsequence_window_with, substring_bytes, substring_bytes_with, SequenceError, WindowMatch};
+pub use suffix_array::{maximal_unique_matches, GeneralizedSuffixArray};
+pub use suffix_automaton::SuffixAutomaton;
+pub use winnowing::{compare_fingerprints, winnow, Fingerprint, SharedPassage};
+use implementations::CommonSubstrings;
+
 /// Given two strings, returns the first found common substring of length k or None if no such
-/// substring exists.
+/// substring exists. The returned match includes where the substring occurs in both strings.
+///
+/// The substring returned is the first common substring in the longer of the two strings (or in s2
+/// if both strings are the same length). Use `SubstringSearch` with a `MatchOrder` to pick a
+/// different order.
 ///
 /// This function uses a hashmap (as per the assignment guidelines).
-pub fn substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<&'a str> {
+pub fn substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
     // The `_naive_prereserve_iter_fx_shorter_substring` function showed the best performance on
     // smaller length substrings. For that reason we rely on it as default implementation.
-    implementations::_naive_prereserve_iter_fx_shorter_substring(s1, s2, k)
+    SubstringSearch::new().strategy(Strategy::FxShorter).k(k).run(s1, s2)
 }
 
 /// Given two strings, returns the a common substring of length k or None if no such substring
-/// exists. If there are multiple common substrings, this function does not guarantee which will be
-/// returned.
+/// exists. The returned match includes where the substring occurs in both strings. If there are
+/// multiple common substrings, this function does not guarantee which will be returned.
 ///
 /// This function runs much faster than `substring()` if a common substring is early on in both
 /// strings, but otherwise tends to run slower.
 ///
 /// This function uses a hashmap (as per the assignment guidelines).
-pub fn unordered_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<&'a str> {
-    implementations::_alternate_prereserve_iter_fx_substring(s1, s2, k)
+pub fn unordered_substring<'a>(s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
+    SubstringSearch::new().strategy(Strategy::Alternate).k(k).run(s1, s2)
+}
+
+/// Given two strings, returns a lazy iterator over all their common substrings of length k (as
+/// matches that include where the substring occurs in both strings). The substrings are returned
+/// in the order they occur in the longer of the two strings, once for every
+/// occurrence. Call `distinct()` on the returned iterator to instead get each common substring only
+/// once.
+///
+/// This function uses the same hashmap of the shorter string's substrings as `substring()`, so the
+/// first substring returned is the same one `substring()` returns.
+pub fn common_substrings<'a>(s1: &'a str, s2: &'a str, k: usize) -> CommonSubstrings<'a> {
+    CommonSubstrings::new(s1, s2, k)
+}
+
+/// Given two strings, returns their longest common substring or None if they don't have any
+/// characters in common. If there are multiple longest common substrings, the one returned is the
+/// one `substring()` returns for that length.
+///
+/// If two strings have a common substring of length k, they also have a common substring of any
+/// length shorter than k. This function therefore binary searches over k. Each probe uses the
+/// rolling polynomial fingerprints of `Strategy::Fingerprint` (which finds the same substring as
+/// `substring()`), so it takes ~O(n) time no matter how large k is, and the whole search takes
+/// ~O(n log(n)) time (where n is the length of the longer string).
+pub fn longest_common_substring<'a>(s1: &'a str, s2: &'a str) -> Option<Match<'a>> {
+    let search = |k| SubstringSearch::new().strategy(Strategy::Fingerprint).k(k).run(s1, s2);
+    let mut longest = search(1)?;
+
+    // Invariant: there is a common substring of length `low` (stored in `longest`), but there is
+    // no common substring longer than `high`.
+    let mut low = 1;
+    let mut high = std::cmp::min(s1.chars().count(), s2.chars().count());
+    while low < high {
+        // Rounds up so that we always make progress
+        let k = low + (high - low).div_ceil(2);
+        match search(k) {
+            Some(m) => {
+                longest = m;
+                low = k;
+            },
+            None => high = k - 1,
+        }
+    }
+    Some(longest)
 }
 
 /// Given two strings, returns if there is a common substring of length k.
@@ -35,7 +113,7 @@ mod tests {
     use std::ops::Range;
     use substring::Substring;
     use proptest::prelude::*;
-    use crate::{substring, unordered_substring};
+    use crate::{common_substrings, longest_common_substring, substring, unordered_substring};
 
     // Current implementation uses the shortest first (to insert into the hash table)
     const SHORTEST_FIRST: bool = true;
@@ -74,7 +152,40 @@ mod tests {
             }
         }
 
-        return None
+        None
+    }
+
+    // Reference implementation for common_substrings to compare against for correctness. Returns
+    // every k-length substring of the longer string (in order) that also appears in the shorter one.
+    fn common_substrings_reference_impl<'a>(s1: &'a str, s2: &'a str, k: usize) -> Vec<&'a str> {
+        let (shorter, longer) = if s1.chars().count() <= s2.chars().count() { (s1, s2) } else { (s2, s1) };
+        let longer_n_chars = longer.chars().count();
+        if longer_n_chars < k {
+            return Vec::new();
+        }
+        (0..(longer_n_chars-k+1))
+            .map(|i| longer.substring(i, i+k))
+            .filter(|sub| shorter.contains(sub))
+            .collect()
+    }
+
+    // Reference implementation for the length of the longest common substring (in characters), using
+    // the standard dynamic programming approach.
+    fn longest_common_substring_len_reference_impl(s1: &str, s2: &str) -> usize {
+        let cs1: Vec<char> = s1.chars().collect();
+        let cs2: Vec<char> = s2.chars().collect();
+        // lengths[i][j] is the length of the longest common suffix of cs1[..i] and cs2[..j]
+        let mut lengths = vec![vec![0; cs2.len()+1]; cs1.len()+1];
+        let mut longest = 0;
+        for i in 1..cs1.len()+1 {
+            for j in 1..cs2.len()+1 {
+                if cs1[i-1] == cs2[j-1] {
+                    lengths[i][j] = lengths[i-1][j-1] + 1;
+                    longest = std::cmp::max(longest, lengths[i][j]);
+                }
+            }
+        }
+        longest
     }
 
     // Checks to see if the provided string `sub` is of length `k` and is a substring of `s1` and
@@ -92,7 +203,7 @@ mod tests {
 
     // Proptest strategy to generate strings where at least one is shorter than k. k must be greater
     // than 0 since it is impossible to generate a string with negative length
-    fn strings_one_shorter_than_k<'a>(k_range: Option<Range<usize>>, string_range: Option<Range<usize>>) -> impl Strategy<Value=(String, String, usize)> {
+    fn strings_one_shorter_than_k(k_range: Option<Range<usize>>, string_range: Option<Range<usize>>) -> impl Strategy<Value=(String, String, usize)> {
         // Defaults
         let _k_range = match k_range {
             // k must be greater than 0
@@ -131,7 +242,7 @@ mod tests {
         // We expect only the first substring will be returned.
         let expected_substring = Some(" test");
 
-        let r = substring(s1, s2, k);
+        let r = substring(s1, s2, k).map(|m| m.text);
         assert_eq!(r, expected_substring);
     }
 
@@ -142,7 +253,7 @@ mod tests {
         let k = 5;
         let expected_substring = None;
 
-        let r = substring(s1, s2, k);
+        let r = substring(s1, s2, k).map(|m| m.text);
         assert_eq!(r, expected_substring);
     }
 
@@ -153,7 +264,7 @@ mod tests {
         let k = 5;
         let expected_substring = None;
 
-        let r = substring(s1, s2, k);
+        let r = substring(s1, s2, k).map(|m| m.text);
         assert_eq!(r, expected_substring);
     }
 
@@ -164,7 +275,7 @@ mod tests {
         let k = 0;
         let expected_substring = Some("");
 
-        let r = substring(s1, s2, k);
+        let r = substring(s1, s2, k).map(|m| m.text);
         assert_eq!(r, expected_substring);
     }
 
@@ -175,8 +286,8 @@ mod tests {
         let s3 = "Uhoh";
         let k = 4;
 
-        assert_eq!(substring(s1, s2, k), Some(s1));
-        assert_eq!(substring(s1, s3, k), None);
+        assert_eq!(substring(s1, s2, k).map(|m| m.text), Some(s1));
+        assert_eq!(substring(s1, s3, k).map(|m| m.text), None);
     }
 
     #[test]
@@ -186,7 +297,7 @@ mod tests {
         let k = 4;
         let expected_substring = None;
 
-        let r = substring(s1, s2, k);
+        let r = substring(s1, s2, k).map(|m| m.text);
         assert_eq!(r, expected_substring);
     }
 
@@ -197,7 +308,7 @@ mod tests {
         fn test_shorter_strings((s1, s2, k) in strings_one_shorter_than_k(Some(1..10), Some(0..20))) {
             let expected_substring = None;
 
-            let r = substring(&s1, &s2, k);
+            let r = substring(&s1, &s2, k).map(|m| m.text);
             assert_eq!(r, expected_substring);
         }
 
@@ -209,7 +320,7 @@ mod tests {
             k in 1..10usize,
         ) {
             let expected_substring = substring_reference_impl(&s1, &s2, k, SHORTEST_FIRST);
-            let r = substring(&s1, &s2, k);
+            let r = substring(&s1, &s2, k).map(|m| m.text);
             assert_eq!(r, expected_substring);
         }
     }
@@ -220,9 +331,9 @@ mod tests {
         let s2 = "Here be another test string. Yaargh. - Pirate";
         let k = 5;
 
-        let r = unordered_substring(s1, s2, k);
-        assert_eq!(r.is_some(), true);
-        assert_eq!(unordered_substring_correct(r.unwrap(), s1, s2, k), true);
+        let r = unordered_substring(s1, s2, k).map(|m| m.text);
+        assert!(r.is_some());
+        assert!(unordered_substring_correct(r.unwrap(), s1, s2, k));
     }
 
     #[test]
@@ -232,7 +343,7 @@ mod tests {
         let k = 5;
         let expected_substring = None;
 
-        let r = unordered_substring(s1, s2, k);
+        let r = unordered_substring(s1, s2, k).map(|m| m.text);
         assert_eq!(r, expected_substring);
     }
 
@@ -243,7 +354,7 @@ mod tests {
         let k = 5;
         let expected_substring = None;
 
-        let r = unordered_substring(s1, s2, k);
+        let r = unordered_substring(s1, s2, k).map(|m| m.text);
         assert_eq!(r, expected_substring);
     }
 
@@ -254,7 +365,7 @@ mod tests {
         let k = 0;
         let expected_substring = Some("");
 
-        let r = unordered_substring(s1, s2, k);
+        let r = unordered_substring(s1, s2, k).map(|m| m.text);
         assert_eq!(r, expected_substring);
     }
 
@@ -265,8 +376,8 @@ mod tests {
         let s3 = "Uhoh";
         let k = 4;
 
-        assert_eq!(unordered_substring(s1, s2, k), Some(s1));
-        assert_eq!(unordered_substring(s1, s3, k), None);
+        assert_eq!(unordered_substring(s1, s2, k).map(|m| m.text), Some(s1));
+        assert_eq!(unordered_substring(s1, s3, k).map(|m| m.text), None);
     }
 
     #[test]
@@ -276,7 +387,7 @@ mod tests {
         let k = 4;
         let expected_substring = None;
 
-        let r = unordered_substring(s1, s2, k);
+        let r = unordered_substring(s1, s2, k).map(|m| m.text);
         assert_eq!(r, expected_substring);
     }
 
@@ -287,7 +398,7 @@ mod tests {
         fn test_shorter_strings_unordered((s1, s2, k) in strings_one_shorter_than_k(Some(1..10), Some(0..20))) {
             let expected_substring = None;
 
-            let r = unordered_substring(&s1, &s2, k);
+            let r = unordered_substring(&s1, &s2, k).map(|m| m.text);
             assert_eq!(r, expected_substring);
         }
 
@@ -298,11 +409,111 @@ mod tests {
             s2 in string_in_range(0..20),
             k in 1..10usize,
         ) {
-            let r = substring(&s1, &s2, k);
+            let r = substring(&s1, &s2, k).map(|m| m.text);
             match r {
                 None => assert_eq!(r, substring_reference_impl(&s1, &s2, k, SHORTEST_FIRST)),
-                Some(sub) => assert_eq!(unordered_substring_correct(sub, &s1, &s2, k), true),
+                Some(sub) => assert!(unordered_substring_correct(sub, &s1, &s2, k)),
             };
         }
     }
+
+    #[test]
+    fn test_common_substrings() {
+        let s1 = "This is a test string. - Normal Person";
+        let s2 = "Here be another test string. Yaargh. - Pirate";
+        let k = 5;
+
+        let r: Vec<&str> = common_substrings(s1, s2, k).map(|m| m.text).collect();
+        assert_eq!(r, vec![" test", "test ", "est s", "st st", "t str", " stri", "strin", "tring", "ring.", "ing. "]);
+    }
+
+    #[test]
+    fn test_common_substrings_distinct() {
+        let s1 = "abcabc";
+        let s2 = "xabcxabcx";
+        let k = 3;
+
+        assert_eq!(common_substrings(s1, s2, k).map(|m| m.text).collect::<Vec<_>>(), vec!["abc", "abc"]);
+        assert_eq!(common_substrings(s1, s2, k).distinct().map(|m| m.text).collect::<Vec<_>>(), vec!["abc"]);
+    }
+
+    #[test]
+    fn test_no_common_substrings() {
+        let s1 = "This is a test string. - Normal Person";
+        let s2 = "Who lives in a pineapple under the sea? - Patchy";
+        let k = 5;
+
+        assert_eq!(common_substrings(s1, s2, k).next(), None);
+        assert_eq!(common_substrings("", s2, k).next(), None);
+    }
+
+    #[test]
+    fn test_no_k_common_substrings() {
+        let s1 = "This is a test string. - Normal Person";
+        let s2 = "Here be another test string. Yaargh. - Pirate";
+
+        assert_eq!(common_substrings(s1, s2, 0).map(|m| m.text).collect::<Vec<_>>(), vec![""]);
+        assert_eq!(common_substrings(s1, s2, 0).distinct().map(|m| m.text).collect::<Vec<_>>(), vec![""]);
+    }
+
+    proptest! {
+        #[test]
+        // Tests behavior against a reference implementation
+        fn test_common_substrings_against_reference(
+            s1 in string_in_range(0..20),
+            s2 in string_in_range(0..20),
+            k in 1..10usize,
+        ) {
+            let expected = common_substrings_reference_impl(&s1, &s2, k);
+            let r: Vec<&str> = common_substrings(&s1, &s2, k).map(|m| m.text).collect();
+            assert_eq!(r, expected.clone());
+            assert_eq!(r.first().copied(), substring(&s1, &s2, k).map(|m| m.text));
+
+            let mut expected_distinct = Vec::new();
+            for sub in expected {
+                if !expected_distinct.contains(&sub) {
+                    expected_distinct.push(sub);
+                }
+            }
+            let r_distinct: Vec<&str> = common_substrings(&s1, &s2, k).distinct().map(|m| m.text).collect();
+            assert_eq!(r_distinct, expected_distinct);
+        }
+    }
+
+    #[test]
+    fn test_longest_common_substring() {
+        let s1 = "This is a test string. - Normal Person";
+        let s2 = "Here be another test string. Yaargh. - Pirate";
+
+        let r = longest_common_substring(s1, s2).unwrap();
+        assert_eq!(r.text, " test string. ");
+        assert_eq!(r.char_len(), 14);
+        assert_eq!(&s1[r.s1_range], r.text);
+        assert_eq!(&s2[r.s2_range], r.text);
+    }
+
+    #[test]
+    fn test_no_longest_common_substring() {
+        assert_eq!(longest_common_substring("abc", "def"), None);
+        assert_eq!(longest_common_substring("", "def"), None);
+        assert_eq!(longest_common_substring("Test", "Test").map(|m| m.text), Some("Test"));
+    }
+
+    proptest! {
+        #[test]
+        // Tests behavior against a reference implementation
+        fn test_longest_common_substring_against_reference(
+            s1 in "[ab€]{0,20}",
+            s2 in "[ab€]{0,20}",
+        ) {
+            let expected_len = longest_common_substring_len_reference_impl(&s1, &s2);
+            match longest_common_substring(&s1, &s2) {
+                None => assert_eq!(expected_len, 0),
+                Some(m) => {
+                    assert_eq!(m.char_len(), expected_len);
+                    assert!(unordered_substring_correct(m.text, &s1, &s2, expected_len));
+                },
+            }
+        }
+    }
 }
@@ -1,12 +1,13 @@
 use std::collections::{HashMap, HashSet};
-use substring_search::substring;
+use substring_search::{substring, Strategy, SubstringFinder};
 use substring_search::helpers::preprocess_string;
 
 use substring_search::implementations::{build_fx_substring, build_rolling_polynomial_substring, build_rolling_adler_substring, build_sip_substring};
 
 /// This function is used to test for frequency of collisions for hash functions. Unit tests are
 /// found in the rust files themselves.
-fn check_collisions<'b>() {
+#[allow(dead_code)]
+fn check_collisions() {
     let files = ["./data/Tolstoy/war_and_peace_tolstoy.txt", "./data/genomes/monkeypox-genome.txt"];
     for file in files {
         let s = preprocess_string(&std::fs::read_to_string(file).unwrap());
@@ -44,5 +45,11 @@ fn main() {
     // let s2 = std::fs::read_to_string("./data/genomes/monkeypox-genome.txt").unwrap();
     println!("Common substring: {:?}", substring(&s1, &s2, 20));
 
+    // The adler implementation is extremely slow on files this large (see the benchmarks), so it's
+    // skipped here too.
+    for finder in Strategy::ALL.into_iter().filter(|finder| *finder != Strategy::RollingAdler) {
+        println!("Common substring ({}): {:?}", finder.name(), finder.find(&s1, &s2, 20).map(|m| m.text));
+    }
+
     // check_collisions()
 }
@@ -0,0 +1,192 @@
+use crate::hashers::mix;
+use crate::implementations::build_rolling_polynomial_substring_with_salt;
+
+/// Salt used for the rolling polynomial hashes of the k-grams. It is fixed (rather than random like
+/// for the substring search implementations) so sketches can be stored and compared across runs.
+pub const MINHASH_SALT: u64 = 0xd1b54a32d192ed03;
+
+// Seed of the i-th hash function. Each hash function is the k-gram's rolling hash, xor-ed with its
+// seed and then mixed.
+fn seed(i: usize) -> u64 {
+    mix((i as u64 + 1).wrapping_mul(0x9e3779b97f4a7c15))
+}
+
+/// MinHash sketch of the set of k-grams of a text. The sketch keeps the smallest value of each of
+/// `num_hashes` hash functions over the k-grams, which is enough to estimate how similar two sets of
+/// k-grams are (and how many k-grams they hold) without storing the sets themselves. Estimates get
+/// more accurate with more hashes: the standard error of the Jaccard estimate is about
+/// 1/sqrt(num_hashes).
+///
+/// Sketches can only be compared or merged if they were built with the same k and number of
+/// hashes. The methods that take another sketch panic otherwise.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MinHashSketch {
+    k: usize,
+    mins: Vec<u64>,
+}
+
+impl MinHashSketch {
+    /// Builds the sketch of the k-grams (of k characters) of `text` using `num_hashes` hash
+    /// functions. The k-grams are hashed with the rolling polynomial hash using `MINHASH_SALT`.
+    pub fn from_text(text: &str, k: usize, num_hashes: usize) -> Self {
+        let mut sketch = MinHashSketch {
+            k,
+            mins: vec![u64::MAX; num_hashes],
+        };
+        let n_chars = text.chars().count();
+        // There are no k-grams if the string has fewer than k characters. We also don't count
+        // empty k-grams.
+        if k == 0 || n_chars < k {
+            return sketch;
+        }
+
+        let seeds: Vec<u64> = (0..num_hashes).map(seed).collect();
+        let mut sub_fn = build_rolling_polynomial_substring_with_salt(text, k, MINHASH_SALT);
+        for _ in 0..(n_chars-k+1) {
+            let (_, hash) = sub_fn();
+            for (min, seed) in sketch.mins.iter_mut().zip(&seeds) {
+                *min = (*min).min(mix(hash ^ seed));
+            }
+        }
+        sketch
+    }
+
+    /// Returns the length (in characters) of the k-grams in the sketch.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the number of hash functions used by the sketch.
+    pub fn num_hashes(&self) -> usize {
+        self.mins.len()
+    }
+
+    /// Returns true if the sketch doesn't hold any k-grams.
+    pub fn is_empty(&self) -> bool {
+        self.mins.iter().all(|&min| min == u64::MAX)
+    }
+
+    fn check_compatible(&self, other: &MinHashSketch) {
+        assert_eq!(self.k, other.k, "Sketches need to use the same k");
+        assert_eq!(self.mins.len(), other.mins.len(), "Sketches need to use the same number of hashes");
+    }
+
+    /// Returns the estimated Jaccard similarity of the two sets of k-grams, i.e. the size of their
+    /// intersection divided by the size of their union. This is the fraction of hash functions for
+    /// which both sets have the same smallest value. Returns 0 if either sketch is empty.
+    pub fn jaccard(&self, other: &MinHashSketch) -> f64 {
+        self.check_compatible(other);
+        if self.mins.is_empty() || self.is_empty() || other.is_empty() {
+            return 0.0;
+        }
+        let same = self.mins.iter().zip(&other.mins).filter(|(a, b)| a == b).count();
+        same as f64 / self.mins.len() as f64
+    }
+
+    /// Returns the estimated number of distinct k-grams in the sketch. The smallest of n random
+    /// values in [0, 1) is about 1/(n+1), so the number of k-grams can be estimated from the average
+    /// of the smallest values.
+    pub fn cardinality(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let m = self.mins.len() as f64;
+        let sum: f64 = self.mins.iter().map(|&min| min as f64 / u64::MAX as f64).sum();
+        // (m - 1) / sum is the unbiased estimator, which isn't defined for a single hash
+        if self.mins.len() > 1 {
+            (m - 1.0) / sum
+        } else {
+            (1.0 / sum - 1.0).max(1.0)
+        }
+    }
+
+    /// Returns the estimated fraction of this sketch's k-grams that are also in `other`. The size
+    /// of the intersection is estimated from the Jaccard similarity and the cardinalities of both
+    /// sketches: |A ∩ B| = J / (1 + J) * (|A| + |B|).
+    pub fn containment(&self, other: &MinHashSketch) -> f64 {
+        let jaccard = self.jaccard(other);
+        if jaccard == 0.0 {
+            return 0.0;
+        }
+        let intersection = jaccard / (1.0 + jaccard) * (self.cardinality() + other.cardinality());
+        (intersection / self.cardinality()).min(1.0)
+    }
+
+    /// Merges `other` into this sketch, which then becomes the sketch of the union of both sets of
+    /// k-grams (e.g. of a whole corpus).
+    pub fn merge(&mut self, other: &MinHashSketch) {
+        self.check_compatible(other);
+        for (min, other_min) in self.mins.iter_mut().zip(&other.mins) {
+            *min = (*min).min(*other_min);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::minhash::MinHashSketch;
+
+    // Text with lots of distinct k-grams, given an offset to pick which ones.
+    fn numbers(offset: usize, n: usize) -> String {
+        (offset..offset + n).map(|i| format!("{:05}", i * 7919 % 100000)).collect::<Vec<_>>().join("|")
+    }
+
+    // Returns the set of k-grams of `text`.
+    fn kgrams(text: &str, k: usize) -> HashSet<Vec<char>> {
+        text.chars().collect::<Vec<_>>().windows(k).map(|w| w.to_vec()).collect()
+    }
+
+    #[test]
+    fn test_minhash_identical_and_disjoint() {
+        let a = MinHashSketch::from_text("This is a test string. - Normal Person", 5, 64);
+        let b = MinHashSketch::from_text("Who lives in a pineapple under the sea? - Patchy", 5, 64);
+
+        assert_eq!(a.jaccard(&a), 1.0);
+        assert_eq!(a.containment(&a), 1.0);
+        assert!(a.jaccard(&b) < 0.1);
+        assert_eq!(a.jaccard(&MinHashSketch::from_text("abc", 5, 64)), 0.0);
+        assert!(MinHashSketch::from_text("abc", 5, 64).is_empty());
+        assert_eq!(MinHashSketch::from_text("abc", 5, 64).cardinality(), 0.0);
+    }
+
+    #[test]
+    fn test_minhash_estimates() {
+        let (text_a, text_b) = (numbers(0, 1000), numbers(500, 1000));
+        let (set_a, set_b) = (kgrams(&text_a, 6), kgrams(&text_b, 6));
+        let intersection = set_a.intersection(&set_b).count() as f64;
+        let union = set_a.union(&set_b).count() as f64;
+
+        let a = MinHashSketch::from_text(&text_a, 6, 512);
+        let b = MinHashSketch::from_text(&text_b, 6, 512);
+        assert!((a.cardinality() / set_a.len() as f64 - 1.0).abs() < 0.15);
+        assert!((b.cardinality() / set_b.len() as f64 - 1.0).abs() < 0.15);
+        assert!((a.jaccard(&b) - intersection / union).abs() < 0.1);
+        assert!((a.containment(&b) - intersection / set_a.len() as f64).abs() < 0.15);
+    }
+
+    #[test]
+    fn test_minhash_merge() {
+        let (text_a, text_b) = (numbers(0, 500), numbers(1000, 500));
+        let a = MinHashSketch::from_text(&text_a, 6, 256);
+        let b = MinHashSketch::from_text(&text_b, 6, 256);
+        let mut merged = a.clone();
+        merged.merge(&b);
+
+        assert!(merged.mins.iter().zip(a.mins.iter().zip(&b.mins)).all(|(m, (x, y))| m == x.min(y)));
+        let (set_a, set_b) = (kgrams(&text_a, 6), kgrams(&text_b, 6));
+        let union = set_a.union(&set_b).count() as f64;
+        assert!((merged.jaccard(&a) - set_a.len() as f64 / union).abs() < 0.1);
+        assert!((merged.cardinality() / union - 1.0).abs() < 0.2);
+        assert!((a.containment(&merged) - 1.0).abs() < 0.15);
+        let mut same = merged.clone();
+        same.merge(&a);
+        assert_eq!(same, merged);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_minhash_incompatible() {
+        MinHashSketch::from_text("abcdef", 2, 16).jaccard(&MinHashSketch::from_text("abcdef", 3, 16));
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::VecDeque;
+use hashbrown::raw::RawTable;
+use crate::hashers::RollingPolynomial;
+use crate::implementations::{build_rolling_polynomial_substring_with_salt, offset_in, Match};
+
+/// A k-length substring that was picked as the minimizer of at least one window.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Minimizer<'a> {
+    /// The substring.
+    pub text: &'a str,
+    /// Byte offset at which the substring starts.
+    pub byte_offset: usize,
+    /// Index (in characters) at which the substring starts.
+    pub char_index: usize,
+    /// Hash of the substring, used to pick the minimizers.
+    pub hash: u64,
+}
+
+/// Returns the (w, k) minimizers of `text`, in order. Each window of w consecutive k-length
+/// substrings is represented by the substring with the smallest hash (the leftmost one if there are
+/// ties), and consecutive windows usually share the same minimizer, so only about 2/(w+1) of the
+/// substrings end up being picked. Strings with fewer than w substrings are represented by the
+/// smallest of their substrings.
+///
+/// The hashes are rolling polynomial hashes using the given salt. Minimizers are only comparable
+/// between strings if they were picked using the same salt.
+pub fn minimizers(text: &str, k: usize, w: usize, salt: u64) -> Vec<Minimizer<'_>> {
+    let n_chars = text.chars().count();
+    // There are no k-length substrings if the string has fewer than k characters. We also don't
+    // return empty substrings.
+    if k == 0 || n_chars < k {
+        return Vec::new();
+    }
+    let w = w.max(1);
+    let n_substrings = n_chars - k + 1;
+
+    let mut sub_fn = build_rolling_polynomial_substring_with_salt(text, k, salt);
+    // Substrings in the current window that could still become the minimizer of a later window,
+    // i.e. with increasing hashes. The front is the minimizer of the current window.
+    let mut candidates: VecDeque<(usize, &str, u64)> = VecDeque::with_capacity(w + 1);
+    let mut result: Vec<Minimizer> = Vec::new();
+
+    for i in 0..n_substrings {
+        let (sub, hash) = sub_fn();
+        // Ties keep the leftmost substring, so it needs to stay in the candidates
+        while candidates.back().is_some_and(|&(_, _, h)| h > hash) {
+            candidates.pop_back();
+        }
+        candidates.push_back((i, sub, hash));
+        if candidates.front().is_some_and(|&(j, _, _)| j + w <= i) {
+            candidates.pop_front();
+        }
+
+        // Only full windows have a minimizer
+        if i + 1 >= w.min(n_substrings) {
+            let &(j, sub, hash) = candidates.front().unwrap();
+            if result.last().is_none_or(|m| m.char_index != j) {
+                result.push(Minimizer {
+                    text: sub,
+                    byte_offset: offset_in(text, sub),
+                    char_index: j,
+                    hash,
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Given two strings, returns a common substring of length k that was picked as a (w, k) minimizer
+/// in both strings, or None if there is no such substring. Only the minimizers of the shorter
+/// string are stored in the hash table, which takes about 2/(w+1) of the memory used by
+/// `substring()`.
+///
+/// Common substrings that are shorter than w + k - 1 characters can be missed, since they may not
+/// contain a minimizer. But any common substring of at least w + k - 1 characters is guaranteed to
+/// be found: it covers a full window of w k-length substrings, which picks the same minimizer in
+/// both strings. Like `substring()`, this returns the first match found in the longer string, but
+/// the position in the shorter string is the first one where the substring was picked as a
+/// minimizer (which isn't necessarily its first occurrence).
+pub fn minimizer_substring<'a>(s1: &'a str, s2: &'a str, k: usize, w: usize) -> Option<Match<'a>> {
+    // Trivial to have matching substrings of length 0
+    if k == 0 {
+        return Some(Match::empty());
+    }
+
+    let cs1_len = s1.chars().count();
+    let cs2_len = s2.chars().count();
+
+    // Choose shorter string to be the one we store in the hash table
+    let (shorter, longer) = if cs1_len <= cs2_len {(s1, s2)} else {(s2, s1)};
+
+    // Both strings need to be hashed using the same salt, otherwise the same substrings would end up
+    // with different hashes (and so different minimizers).
+    let salt = RollingPolynomial::random_odd_salt();
+    let short_minimizers = minimizers(shorter, k, w, salt);
+
+    // We need to use a `RawTable` to ensure that we can re-use our previously computed rolling
+    // hash.
+    let mut substrings: RawTable<(&str, (usize, usize))> = RawTable::with_capacity(short_minimizers.len());
+    for m in short_minimizers {
+        // We only insert into the table if it's not already in there, which also means we keep
+        // track of where a minimizer first occurs in the shorter string.
+        if substrings.find(m.hash, |(other, _)| *other == m.text).is_none() {
+            substrings.try_insert_no_grow(m.hash, (m.text, (m.byte_offset, m.char_index))).unwrap();
+        }
+    }
+
+    for m in minimizers(longer, k, w, salt) {
+        if let Some((_, short_start)) = substrings.get(m.hash, |(other, _)| *other == m.text) {
+            // Substring found in both s1 and s2, can return early. The match positions need to be
+            // swapped back if s2 was the shorter string.
+            let found = Match::new(m.text, *short_start, (m.byte_offset, m.char_index));
+            return Some(if cs1_len <= cs2_len { found } else { found.swap() });
+        }
+    }
+
+    // No minimizer of the longer string is also a minimizer of the shorter string.
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use crate::minimizer::{minimizer_substring, minimizers};
+    use crate::substring;
+
+    #[test]
+    fn test_minimizers() {
+        let text = "›It costs €10 for this item… or €12 for that one";
+        let (k, w) = (4, 5);
+        let found = minimizers(text, k, w, 0x9e3779b97f4a7c15);
+
+        // Every window has to contain a minimizer, so they can't be more than w substrings apart
+        assert!(found.windows(2).all(|pair| pair[0].char_index < pair[1].char_index && pair[1].char_index - pair[0].char_index <= w));
+        assert!(found[0].char_index < w);
+        assert!(found.len() < text.chars().count() - k + 1);
+        for m in &found {
+            assert_eq!(&text[m.byte_offset..m.byte_offset + m.text.len()], m.text);
+            assert_eq!(m.text.chars().count(), k);
+        }
+
+        // Strings with fewer than w substrings still get one minimizer
+        assert_eq!(minimizers("abcde", 4, 5, 3).len(), 1);
+        assert_eq!(minimizers("abc", 4, 5, 3), vec![]);
+    }
+
+    #[test]
+    fn test_minimizer_substring() {
+        let s1 = "This is a test string. - Normal Person";
+        let s2 = "Here be another test string. Yaargh. - Pirate";
+
+        let m = minimizer_substring(s1, s2, 5, 10).unwrap();
+        assert_eq!(&s1[m.s1_range], m.text);
+        assert_eq!(&s2[m.s2_range], m.text);
+        assert_eq!(minimizer_substring(s1, s2, 15, 2), None);
+    }
+
+    proptest! {
+        #[test]
+        // Any common substring of at least w + k - 1 characters has to be found.
+        fn test_minimizer_substring_guarantee(
+            prefix1 in "[abc€]{0,20}",
+            prefix2 in "[abc€]{0,20}",
+            shared in "[abc€]{12}",
+            suffix in "[abc€]{0,20}",
+            k in 1..6usize,
+            w in 1..8usize,
+        ) {
+            let s1 = format!("{}{}", prefix1, shared);
+            let s2 = format!("{}{}{}", prefix2, shared, suffix);
+            let m = minimizer_substring(&s1, &s2, k, w).unwrap();
+            assert_eq!(m.char_len(), k);
+            assert_eq!(&s1[m.s1_range], m.text);
+            assert_eq!(&s2[m.s2_range], m.text);
+        }
+
+        #[test]
+        fn test_minimizer_substring_is_common(
+            s1 in "[abc€]{0,20}",
+            s2 in "[abc€]{0,20}",
+            k in 0..6usize,
+            w in 1..8usize,
+        ) {
+            if minimizer_substring(&s1, &s2, k, w).is_some() {
+                assert!(substring(&s1, &s2, k).is_some());
+            }
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::ops::Range;
+use rustc_hash::FxHashMap;
+use crate::implementations::KGrams;
+
+/// A k-length substring shared by several documents, see `common_substring_n()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SharedSubstring<'a> {
+    /// The shared substring.
+    pub text: &'a str,
+    /// The documents (as indices into the input slice) that contain the substring, in increasing
+    /// order. Each document is listed along with the byte range of the first occurrence of the
+    /// substring in it.
+    pub documents: Vec<(usize, Range<usize>)>,
+}
+
+/// Given a list of documents, returns every k-length substring that occurs in at least `min_docs`
+/// of them (a `min_docs` of 0 is treated as 1). The substrings are ordered by the first document
+/// they occur in and then by where they occur in it.
+///
+/// This extends the approach of `_naive_prereserve_iter_fx_shorter_substring()`. A substring that
+/// occurs in at least `min_docs` of the n documents has to occur in at least one of any
+/// n - min_docs + 1 documents, so only the k-length substrings of the n - min_docs + 1 shortest
+/// documents are put in the hash table. The remaining documents are then only checked against the
+/// table. When looking for substrings shared by all documents, only the shortest document is put
+/// in the hash table.
+pub fn common_substring_n<'a>(documents: &[&'a str], k: usize, min_docs: usize) -> Vec<SharedSubstring<'a>> {
+    let min_docs = std::cmp::max(min_docs, 1);
+    if documents.len() < min_docs {
+        return Vec::new();
+    }
+
+    // Trivial to have matching substrings of length 0
+    if k == 0 {
+        return vec![SharedSubstring {
+            text: "",
+            documents: (0..documents.len()).map(|d| (d, 0..0)).collect(),
+        }];
+    }
+
+    // Sort the documents by length so the shortest ones are put in the hash table.
+    let n_chars: Vec<usize> = documents.iter().map(|d| d.chars().count()).collect();
+    let mut by_length: Vec<usize> = (0..documents.len()).collect();
+    by_length.sort_by_key(|&d| n_chars[d]);
+    let (indexed, checked) = by_length.split_at(documents.len() - min_docs + 1);
+
+    // Maps each substring to its index in `shared`.
+    let mut substrings: FxHashMap<&str, usize> = FxHashMap::default();
+    // Note: we reserve space to guarantee that the hash map can hold at least `capacity` elements
+    // without reallocating.
+    substrings.reserve(indexed.iter().map(|&d| n_chars[d]).sum());
+    let mut shared: Vec<SharedSubstring> = Vec::new();
+
+    // Records that document `d` contains the substring (unless it has already been recorded, in
+    // which case this isn't the first occurrence).
+    fn record(entry: &mut SharedSubstring, d: usize, range: Range<usize>) {
+        if entry.documents.last().map(|(last, _)| *last) != Some(d) {
+            entry.documents.push((d, range));
+        }
+    }
+
+    for &d in indexed {
+        for (_, start, sub) in KGrams::new(documents[d], k) {
+            let i = *substrings.entry(sub).or_insert_with(|| {
+                shared.push(SharedSubstring { text: sub, documents: Vec::new() });
+                shared.len() - 1
+            });
+            record(&mut shared[i], d, start..start + sub.len());
+        }
+    }
+
+    for &d in checked {
+        for (_, start, sub) in KGrams::new(documents[d], k) {
+            if let Some(&i) = substrings.get(sub) {
+                record(&mut shared[i], d, start..start + sub.len());
+            }
+        }
+    }
+
+    let mut shared: Vec<SharedSubstring> = shared.into_iter()
+        .filter(|s| s.documents.len() >= min_docs)
+        .map(|mut s| {
+            s.documents.sort_by_key(|(d, _)| *d);
+            s
+        })
+        .collect();
+    shared.sort_by_key(|s| (s.documents[0].0, s.documents[0].1.start));
+    shared
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use proptest::prelude::*;
+    use crate::multi::common_substring_n;
+
+    // Reference implementation that checks every k-length substring of every document against all
+    // the documents. Returns the shared substrings along with the documents they occur in.
+    fn common_substring_n_reference_impl(documents: &[&str], k: usize, min_docs: usize) -> HashSet<(String, Vec<usize>)> {
+        let mut shared = HashSet::new();
+        for document in documents {
+            let cs: Vec<char> = document.chars().collect();
+            if cs.len() < k {
+                continue;
+            }
+            for window in cs.windows(k) {
+                let sub: String = window.iter().collect();
+                let containing: Vec<usize> = (0..documents.len()).filter(|&d| documents[d].contains(&sub)).collect();
+                if containing.len() >= std::cmp::max(min_docs, 1) {
+                    shared.insert((sub, containing));
+                }
+            }
+        }
+        shared
+    }
+
+    #[test]
+    fn test_common_substring_all_documents() {
+        let documents = [
+            "This is a test string. - Normal Person",
+            "Here be another test string. Yaargh. - Pirate",
+            "Who lives in a pineapple under the sea? Not a test string. - Patchy",
+        ];
+        let k = 8;
+
+        let shared = common_substring_n(&documents, k, 3);
+        let texts: Vec<&str> = shared.iter().map(|s| s.text).collect();
+        assert_eq!(texts, vec![" test st", "test str", "est stri", "st strin", "t string", " string.", "string. "]);
+        for s in shared {
+            assert_eq!(s.documents.iter().map(|(d, _)| *d).collect::<Vec<_>>(), vec![0, 1, 2]);
+            for (d, range) in s.documents {
+                assert_eq!(&documents[d][range], s.text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_common_substring_some_documents() {
+        let documents = ["abcd", "xyzw", "zwab"];
+
+        let shared = common_substring_n(&documents, 2, 2);
+        assert_eq!(shared.len(), 2);
+        assert_eq!(shared[0].text, "ab");
+        assert_eq!(shared[0].documents, vec![(0, 0..2), (2, 2..4)]);
+        assert_eq!(shared[1].text, "zw");
+        assert_eq!(shared[1].documents, vec![(1, 2..4), (2, 0..2)]);
+
+        assert!(common_substring_n(&documents, 2, 3).is_empty());
+        assert!(common_substring_n(&documents, 2, 4).is_empty());
+    }
+
+    proptest! {
+        #[test]
+        // Tests behavior against a reference implementation
+        fn test_common_substring_n_against_reference(
+            documents in prop::collection::vec("[abc€]{0,12}", 0..5),
+            k in 1..4usize,
+            min_docs in 0..6usize,
+        ) {
+            let documents: Vec<&str> = documents.iter().map(|d| d.as_str()).collect();
+            let expected = common_substring_n_reference_impl(&documents, k, min_docs);
+            let r: HashSet<(String, Vec<usize>)> = common_substring_n(&documents, k, min_docs).into_iter()
+                .map(|s| (s.text.to_string(), s.documents.iter().map(|(d, _)| *d).collect()))
+                .collect();
+            assert_eq!(r, expected);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use rustc_hash::FxHashMap;
+use crate::implementations::{build_fx_table, KGrams};
+
+/// Statistics about the k-length substrings (k-grams) that two strings have in common.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KGramOverlap {
+    /// Number of distinct k-grams in s1.
+    pub s1_distinct: usize,
+    /// Number of distinct k-grams in s2.
+    pub s2_distinct: usize,
+    /// Number of distinct k-grams that occur in both strings.
+    pub distinct_shared: usize,
+    /// Number of positions in s1 at which a k-gram that also occurs in s2 starts.
+    pub s1_shared_occurrences: usize,
+    /// Number of positions in s2 at which a k-gram that also occurs in s1 starts.
+    pub s2_shared_occurrences: usize,
+    /// Fraction of the distinct k-grams of s1 that also occur in s2 (0 if s1 has no k-grams).
+    pub s1_containment: f64,
+    /// Fraction of the distinct k-grams of s2 that also occur in s1 (0 if s2 has no k-grams).
+    pub s2_containment: f64,
+    /// Fraction of the characters of s1 that are part of a k-gram that also occurs in s2 (0 if s1
+    /// is empty).
+    pub s1_coverage: f64,
+    /// Fraction of the characters of s2 that are part of a k-gram that also occurs in s1 (0 if s2
+    /// is empty).
+    pub s2_coverage: f64,
+}
+
+// Returns `numerator / denominator`, or 0 if the denominator is 0.
+fn fraction(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+// Returns the number of positions in `source` at which a k-gram from `other` starts, and the number
+// of characters of `source` covered by those k-grams.
+fn shared_occurrences(source: &str, k: usize, other: &FxHashMap<&str, (usize, usize)>) -> (usize, usize) {
+    let mut occurrences = 0;
+    let mut covered = 0;
+    // Characters before this index are already counted as covered
+    let mut covered_until = 0;
+    for (i, _, sub) in KGrams::new(source, k) {
+        if other.contains_key(sub) {
+            occurrences += 1;
+            covered += i + k - covered_until.max(i);
+            covered_until = i + k;
+        }
+    }
+    (occurrences, covered)
+}
+
+/// Given two strings, returns statistics about the k-length substrings (of k characters) they have
+/// in common: how many there are, how often they occur, how much of each string's k-grams they make
+/// up and how much of each string they cover. There are no k-grams of length 0, so all the
+/// statistics are 0 when k = 0.
+///
+/// This function sticks the k-grams of each string in its own pre-reserved hash table (the same one
+/// `substring()` uses, with the firefox hashing algorithm), then goes over the k-grams of each
+/// string again to check which ones are in the other string's table. Runs in ~O(n) time (where n
+/// is the combined length of the strings).
+pub fn kgram_overlap(s1: &str, s2: &str, k: usize) -> KGramOverlap {
+    let cs1_len = s1.chars().count();
+    let cs2_len = s2.chars().count();
+
+    let table1 = build_fx_table(s1, cs1_len, k);
+    let table2 = build_fx_table(s2, cs2_len, k);
+    // Iterates over the smaller table to find the intersection
+    let distinct_shared = if table1.len() <= table2.len() {
+        table1.keys().filter(|sub| table2.contains_key(*sub)).count()
+    } else {
+        table2.keys().filter(|sub| table1.contains_key(*sub)).count()
+    };
+
+    let (s1_shared_occurrences, s1_covered) = shared_occurrences(s1, k, &table2);
+    let (s2_shared_occurrences, s2_covered) = shared_occurrences(s2, k, &table1);
+
+    KGramOverlap {
+        s1_distinct: table1.len(),
+        s2_distinct: table2.len(),
+        distinct_shared,
+        s1_shared_occurrences,
+        s2_shared_occurrences,
+        s1_containment: fraction(distinct_shared, table1.len()),
+        s2_containment: fraction(distinct_shared, table2.len()),
+        s1_coverage: fraction(s1_covered, cs1_len),
+        s2_coverage: fraction(s2_covered, cs2_len),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use proptest::prelude::*;
+    use crate::overlap::{kgram_overlap, KGramOverlap};
+
+    // Reference implementation that computes the statistics directly from the k-gram windows.
+    fn kgram_overlap_reference_impl(s1: &str, s2: &str, k: usize) -> KGramOverlap {
+        let c1: Vec<char> = s1.chars().collect();
+        let c2: Vec<char> = s2.chars().collect();
+        let windows = |c: &Vec<char>| -> Vec<Vec<char>> {
+            if k == 0 { vec![] } else { c.windows(k).map(|w| w.to_vec()).collect() }
+        };
+        let (w1, w2) = (windows(&c1), windows(&c2));
+        let set1: HashSet<&Vec<char>> = w1.iter().collect();
+        let set2: HashSet<&Vec<char>> = w2.iter().collect();
+        let shared = set1.intersection(&set2).count();
+        let covered = |c: &Vec<char>, w: &Vec<Vec<char>>, other: &HashSet<&Vec<char>>| {
+            (0..c.len()).filter(|&p| (0..w.len()).any(|i| i <= p && p < i + k && other.contains(&w[i]))).count()
+        };
+        let fraction = |a: usize, b: usize| if b == 0 { 0.0 } else { a as f64 / b as f64 };
+
+        KGramOverlap {
+            s1_distinct: set1.len(),
+            s2_distinct: set2.len(),
+            distinct_shared: shared,
+            s1_shared_occurrences: w1.iter().filter(|w| set2.contains(w)).count(),
+            s2_shared_occurrences: w2.iter().filter(|w| set1.contains(w)).count(),
+            s1_containment: fraction(shared, set1.len()),
+            s2_containment: fraction(shared, set2.len()),
+            s1_coverage: fraction(covered(&c1, &w1, &set2), c1.len()),
+            s2_coverage: fraction(covered(&c2, &w2, &set1), c2.len()),
+        }
+    }
+
+    #[test]
+    fn test_kgram_overlap() {
+        let overlap = kgram_overlap("abcabcx", "zabcab", 3);
+        assert_eq!(overlap, KGramOverlap {
+            s1_distinct: 4,
+            s2_distinct: 4,
+            distinct_shared: 3,
+            s1_shared_occurrences: 4,
+            s2_shared_occurrences: 3,
+            s1_containment: 0.75,
+            s2_containment: 0.75,
+            s1_coverage: 6.0 / 7.0,
+            s2_coverage: 5.0 / 6.0,
+        });
+        assert_eq!(kgram_overlap("abc", "abc", 0).distinct_shared, 0);
+        assert_eq!(kgram_overlap("", "abc", 1).s1_coverage, 0.0);
+    }
+
+    proptest! {
+        #[test]
+        fn test_kgram_overlap_against_reference(
+            s1 in "[abc€]{0,20}",
+            s2 in "[abc€]{0,20}",
+            k in 0..6usize,
+        ) {
+            assert_eq!(kgram_overlap(&s1, &s2, k), kgram_overlap_reference_impl(&s1, &s2, k));
+        }
+    }
+}
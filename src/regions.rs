@@ -0,0 +1,123 @@
+use rustc_hash::FxHashMap;
+use crate::implementations::{build_fx_positions_table, Match};
+
+/// Given two strings, returns the regions they share that contain a common substring of length k,
+/// extended to the left and right as far as the strings keep matching. Unlike
+/// `common_substrings()`, a shared passage of n characters is reported once instead of as n-k+1
+/// overlapping substrings. The regions are sorted by their position in s1, then in s2.
+///
+/// The seeds are all the pairs of positions at which the same substring of length k occurs in
+/// both strings: the k-length windows of the shorter string are stuck in a hash map along with
+/// every position they occur at, then the windows of the longer string are looked up in order.
+/// Each seed is extended character by character in both strings, unless it lies within a region
+/// already found on the same diagonal (i.e. with the same offset between its positions in s1 and
+/// s2), so overlapping seeds are merged into a single region. Since every occurrence is seeded,
+/// passages that repeat in either string are reported once for each pair of occurrences. There
+/// are no meaningful seeds of length 0, so k is at least 1.
+pub fn shared_regions<'a>(s1: &'a str, s2: &'a str, k: usize) -> Vec<Match<'a>> {
+    let k = k.max(1);
+    let (s1_offsets, c1): (Vec<usize>, Vec<char>) = s1.char_indices().unzip();
+    let (s2_offsets, c2): (Vec<usize>, Vec<char>) = s2.char_indices().unzip();
+    let byte_offset = |offsets: &[usize], source: &str, i: usize| offsets.get(i).copied().unwrap_or(source.len());
+
+    // Choose shorter string to be the one we store in the hash map
+    let swapped = c2.len() < c1.len();
+    let (shorter, longer) = if swapped {(&c2, &c1)} else {(&c1, &c2)};
+    let kgrams = build_fx_positions_table(shorter.as_slice(), shorter.len(), k);
+
+    let mut regions = Vec::new();
+    // End (as a char index in s1) of the last region found on each diagonal. Seeds come in order of
+    // their position in the longer string, so a seed can only be within the last region found on
+    // its diagonal.
+    let mut region_ends: FxHashMap<isize, usize> = FxHashMap::default();
+    let seeds = longer.windows(k).enumerate().flat_map(|(long_start, window)| {
+        kgrams.get(window).into_iter().flatten().map(move |&short_start| {
+            if swapped { (long_start, short_start) } else { (short_start, long_start) }
+        })
+    });
+    for (i, j) in seeds {
+        let diagonal = j as isize - i as isize;
+        if region_ends.get(&diagonal).is_some_and(|&end| i < end) {
+            continue;
+        }
+
+        let left = c1[..i].iter().rev().zip(c2[..j].iter().rev()).take_while(|(a, b)| a == b).count();
+        let right = c1[i + k..].iter().zip(&c2[j + k..]).take_while(|(a, b)| a == b).count();
+        let (start1, start2, end1) = (i - left, j - left, i + k + right);
+        region_ends.insert(diagonal, end1);
+
+        let s1_start = byte_offset(&s1_offsets, s1, start1);
+        let text = &s1[s1_start..byte_offset(&s1_offsets, s1, end1)];
+        regions.push(Match::new(text, (s1_start, start1), (byte_offset(&s2_offsets, s2, start2), start2)));
+    }
+
+    regions.sort_by_key(|m| (m.s1_char_index, m.s2_char_index));
+    regions
+}
+
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use crate::regions::shared_regions;
+
+    #[test]
+    fn test_shared_regions() {
+        let passage = "Happy families are all alike; every unhappy family is unhappy in its own way.";
+        let s1 = format!("Chapter 1. {} Everything was in confusion.", passage);
+        let s2 = format!("As the novel opens: {} And so on.", passage);
+
+        let regions = shared_regions(&s1, &s2, 20);
+        assert_eq!(regions.len(), 1);
+        // The region extends past the passage as far as both strings keep matching
+        assert_eq!(regions[0].text, format!(" {} ", passage));
+        assert_eq!(&s2[regions[0].s2_range.clone()], regions[0].text);
+        assert_eq!(regions[0].s1_char_index, 10);
+        assert_eq!(shared_regions(&s1, &s2, 100), vec![]);
+
+        // A passage that repeats in the shorter string is reported for both occurrences
+        let regions = shared_regions("abcdeXabcdeY", "QQabcdeZZZZZZZZZ", 5);
+        let found: Vec<_> = regions.iter().map(|m| (m.text, m.s1_char_index, m.s2_char_index)).collect();
+        assert_eq!(found, vec![("abcde", 0, 2), ("abcde", 6, 2)]);
+    }
+
+    proptest! {
+        #[test]
+        fn test_shared_regions_maximal(
+            s1 in "[abc€]{0,20}",
+            s2 in "[abc€]{0,20}",
+            k in 0..6usize,
+        ) {
+            let c1: Vec<char> = s1.chars().collect();
+            let c2: Vec<char> = s2.chars().collect();
+            let regions = shared_regions(&s1, &s2, k);
+            for m in &regions {
+                let (i, j, len) = (m.s1_char_index, m.s2_char_index, m.char_len());
+                assert!(len >= k.max(1));
+                assert_eq!(&s1[m.s1_range.clone()], m.text);
+                assert_eq!(&s2[m.s2_range.clone()], m.text);
+                // Can't be extended to the left or the right
+                assert!(i == 0 || j == 0 || c1[i - 1] != c2[j - 1]);
+                assert!(i + len == c1.len() || j + len == c2.len() || c1[i + len] != c2[j + len]);
+            }
+            // Regions are distinct, and every pair of occurrences of a common substring is covered
+            // by one of them
+            let mut pairs: Vec<_> = regions.iter().map(|m| (m.s1_char_index, m.s2_char_index)).collect();
+            pairs.dedup();
+            assert_eq!(pairs.len(), regions.len());
+            let k = k.max(1);
+            for (i, w1) in c1.windows(k).enumerate() {
+                for (j, w2) in c2.windows(k).enumerate() {
+                    if w1 != w2 {
+                        continue;
+                    }
+                    assert!(regions.iter().any(|m| {
+                        m.s2_char_index as isize - m.s1_char_index as isize == j as isize - i as isize
+                            && m.s1_char_index <= i
+                            && i + k <= m.s1_char_index + m.char_len()
+                    }));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,763 @@
+#![allow(deprecated)]
+
+use rustc_hash::FxHashMap;
+use crate::bloom::BloomFilterConfig;
+use crate::dna::{dna_common_substrings, MAX_DNA_K};
+use crate::hashers::RollingPolynomial;
+use crate::implementations::{self, Match};
+use crate::suffix_automaton::SuffixAutomaton;
+
+// Signature shared by all the substring search implementations.
+pub(crate) type Implementation = for<'a> fn(&'a str, &'a str, usize) -> Option<Match<'a>>;
+
+/// The implementations that can be used to search for a common substring. Each one finds the same
+/// common substrings, but which one performs best depends on the inputs (see the README). The
+/// documentation of the corresponding function in `implementations` describes how each one works.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Strategy {
+    /// Uses `_naive_substring()`.
+    Naive,
+    /// Uses `_naive_prereserve_substring()`.
+    NaivePrereserve,
+    /// Uses `_naive_prereserve_iter_substring()`.
+    NaivePrereserveIter,
+    /// Uses `_naive_prereserve_iter_fx_substring()`.
+    Fx,
+    /// Uses `_naive_prereserve_iter_fx_shorter_substring()`. This is the strategy `substring()`
+    /// uses.
+    #[default]
+    FxShorter,
+    /// Uses `_naive_prereserve_iter_rolling_adler_shorter_substring()`.
+    RollingAdler,
+    /// Uses `_naive_prereserve_iter_rolling_poly_shorter_substring()`.
+    RollingPoly,
+    /// Uses `_alternate_prereserve_iter_fx_substring()`. This is the strategy
+    /// `unordered_substring()` uses.
+    Alternate,
+    /// Uses `_suffix_automaton_shorter_substring()`.
+    SuffixAutomaton,
+    /// Uses `_fingerprint_rolling_poly_shorter_substring()`, which only stores 64-bit fingerprints
+    /// in the hash table. Use `SubstringSearch::fingerprint_width()` to store wider fingerprints.
+    Fingerprint,
+    /// Uses `_fingerprint128_rolling_poly_shorter_substring()`, which only stores 128-bit
+    /// fingerprints in the hash table (for a lower false positive rate than `Fingerprint`).
+    Fingerprint128,
+    /// Uses `_dna_packed_shorter_substring()`, which packs DNA k-mers into 64-bit integers instead
+    /// of hashing substrings (and falls back to `Strategy::FxShorter` for other inputs).
+    PackedDna,
+    /// Picks one of the other strategies based on the length of the inputs, a sample of them and on
+    /// k. Use `Strategy::resolve()` to see which strategy gets picked.
+    Auto,
+}
+
+/// Which common substring gets returned when two strings have several common substrings of length k.
+/// Positions in the returned match always refer to the first occurrence of the substring in each
+/// string.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MatchOrder {
+    /// Whichever common substring the strategy finds first. This is the fastest option. For the
+    /// strategies that put the shorter string in the hash table (like the one `substring()` uses),
+    /// this is the first common substring in the longer string (or in s2 if both strings are the
+    /// same length).
+    #[default]
+    Any,
+    /// The common substring that occurs first in s2.
+    FirstInS2,
+    /// The common substring that occurs first in s1.
+    FirstInS1,
+    /// The common substring with the smallest max(position in s1, position in s2). Ties are broken
+    /// by the position in s2.
+    SmallestMaxPosition,
+    /// The lexicographically smallest common substring.
+    Lexicographic,
+}
+
+/// Width of the fingerprints stored by `Strategy::Fingerprint`, which trades the memory taken by
+/// the hash table for its false positive rate (see `SubstringSearch::fingerprint_width()`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FingerprintWidth {
+    /// 64-bit fingerprints (8 bytes per table entry), see
+    /// `_fingerprint_rolling_poly_shorter_substring()`.
+    #[default]
+    Bits64,
+    /// 128-bit fingerprints (16 bytes per table entry) with a false positive rate bounded for any
+    /// input, see `_fingerprint128_rolling_poly_shorter_substring()`.
+    Bits128,
+}
+
+// Number of characters sampled from the start of each input by `Strategy::Auto`.
+const AUTO_SAMPLE_CHARS: usize = 4096;
+// Inputs whose sample is (almost entirely) made up of at most this many distinct characters are
+// considered to draw from a very limited vocabulary, like DNA bases.
+const AUTO_LIMITED_ALPHABET: usize = 8;
+// Fraction of the sampled characters used to measure the size of the alphabet. This keeps a few
+// rare characters (e.g. the header line of a genome file) from making the alphabet look rich.
+const AUTO_ALPHABET_COVERAGE: f64 = 0.95;
+// Smallest k for which `Strategy::Auto` considers the rolling polynomial hash.
+const AUTO_ROLLING_POLY_MIN_K: usize = 40;
+// Inputs whose combined length (in bytes) is at most this are searched naively by `Strategy::Auto`.
+const AUTO_TINY_INPUT_BYTES: usize = 64;
+// Inputs whose combined length (in bytes) is at least this are searched with fingerprints by
+// `Strategy::Auto`.
+const AUTO_LARGE_INPUT_BYTES: usize = 1 << 24;
+
+// Returns the first `n` characters of `s` (or all of `s` if it is shorter).
+fn prefix(s: &str, n: usize) -> &str {
+    match s.char_indices().nth(n) {
+        Some((i, _)) => &s[..i],
+        None => s,
+    }
+}
+
+// Returns true if `sample` only contains the bases that `dna_substring()` can pack (A, C, G, T and
+// the unknown base N).
+fn is_dna(sample: &str) -> bool {
+    sample.bytes().all(|b| matches!(b, b'A' | b'C' | b'G' | b'T' | b'N'))
+}
+
+// Returns the number of distinct characters that make up the given fraction of `sample`.
+fn alphabet_size(sample: &str, coverage: f64) -> usize {
+    let mut counts: FxHashMap<char, usize> = FxHashMap::default();
+    let mut total = 0;
+    for c in sample.chars() {
+        *counts.entry(c).or_insert(0) += 1;
+        total += 1;
+    }
+    let mut counts: Vec<usize> = counts.into_values().collect();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut covered = 0;
+    for (i, count) in counts.iter().enumerate() {
+        if covered as f64 >= coverage * total as f64 {
+            return i;
+        }
+        covered += count;
+    }
+    counts.len()
+}
+
+impl Strategy {
+    /// All the available strategies (other than `Strategy::Auto`, which picks one of these).
+    pub const ALL: [Strategy; 12] = [
+        Strategy::Naive,
+        Strategy::NaivePrereserve,
+        Strategy::NaivePrereserveIter,
+        Strategy::Fx,
+        Strategy::FxShorter,
+        Strategy::RollingAdler,
+        Strategy::RollingPoly,
+        Strategy::Alternate,
+        Strategy::SuffixAutomaton,
+        Strategy::Fingerprint,
+        Strategy::Fingerprint128,
+        Strategy::PackedDna,
+    ];
+
+    /// Returns the strategy that is used to search the given inputs. This is the strategy itself,
+    /// unless it is `Strategy::Auto`.
+    ///
+    /// `Strategy::Auto` follows the results of the benchmarks (see the README). It looks at the
+    /// combined length of the inputs, samples the start of both inputs and picks:
+    ///   1) `Strategy::Naive` if the inputs are tiny, since there's nothing to gain from
+    ///      pre-reserving tables (or even from sampling the inputs).
+    ///   2) `Strategy::Alternate` if the samples already have a common substring, since the
+    ///      alternating search performs best when a common substring is very early in both inputs.
+    ///   3) `Strategy::PackedDna` if both samples are DNA sequences (with the FASTA headers and
+    ///      newlines stripped, see `fasta_sequence()`) and the k-mers fit in 64 bits, since packed
+    ///      k-mers don't need to be hashed or compared as strings. It falls back to
+    ///      `Strategy::FxShorter` if the rest of the inputs turn out not to be DNA.
+    ///   4) `Strategy::Fingerprint` if the inputs are very large, since its hash table only stores
+    ///      64-bit fingerprints instead of substrings and stays much smaller.
+    ///   5) `Strategy::FxShorter` if the inputs draw from a very limited vocabulary (like DNA bases)
+    ///      or if k is small.
+    ///   6) `Strategy::RollingPoly` otherwise, since the rolling hash performs best for larger values
+    ///      of k.
+    pub fn resolve(self, s1: &str, s2: &str, k: usize) -> Strategy {
+        if self != Strategy::Auto {
+            return self;
+        }
+
+        let input_len = s1.len() + s2.len();
+        if input_len <= AUTO_TINY_INPUT_BYTES {
+            return Strategy::Naive;
+        }
+
+        let sample1 = prefix(s1, AUTO_SAMPLE_CHARS);
+        let sample2 = prefix(s2, AUTO_SAMPLE_CHARS);
+        if Strategy::FxShorter.find(sample1, sample2, k).is_some() {
+            return Strategy::Alternate;
+        }
+        if k <= MAX_DNA_K && is_dna(sample1) && is_dna(sample2) {
+            return Strategy::PackedDna;
+        }
+        if input_len >= AUTO_LARGE_INPUT_BYTES {
+            return Strategy::Fingerprint;
+        }
+
+        let limited_alphabet = alphabet_size(sample1, AUTO_ALPHABET_COVERAGE) <= AUTO_LIMITED_ALPHABET
+            || alphabet_size(sample2, AUTO_ALPHABET_COVERAGE) <= AUTO_LIMITED_ALPHABET;
+        if limited_alphabet || k < AUTO_ROLLING_POLY_MIN_K {
+            Strategy::FxShorter
+        } else {
+            Strategy::RollingPoly
+        }
+    }
+
+    /// Given two strings, returns the common substring of length k that comes first in the given
+    /// order, or None if there is no common substring of length k.
+    ///
+    /// If the strategy naturally finds matches in the requested order (e.g. a strategy that scans
+    /// s2 looking for substrings of s1 for `MatchOrder::FirstInS2`) it can still return early. Otherwise
+    /// all the common substrings need to be found (using the strategy's `find_all()`) before the
+    /// first one in the requested order can be returned, which is slower.
+    pub fn find_ordered<'a>(self, s1: &'a str, s2: &'a str, k: usize, order: MatchOrder) -> Option<Match<'a>> {
+        self.find_filtered(s1, s2, k, order, None)
+    }
+
+    // Same as `find_ordered()`, but checks a Bloom filter with the given config before probing the
+    // hash table if the strategy supports it.
+    fn find_filtered<'a>(self, s1: &'a str, s2: &'a str, k: usize, order: MatchOrder, bloom: Option<BloomFilterConfig>) -> Option<Match<'a>> {
+        let strategy = self.resolve(s1, s2, k);
+        // Trivial to have matching substrings of length 0, no need to order them.
+        if order == MatchOrder::Any || k == 0 {
+            return strategy.find_prefiltered(s1, s2, k, bloom);
+        }
+
+        let cs1_len = s1.chars().count();
+        let cs2_len = s2.chars().count();
+        match order {
+            MatchOrder::FirstInS2 if strategy.scans_second(cs1_len, cs2_len) => {
+                return strategy.find_prefiltered(s1, s2, k, bloom);
+            },
+            MatchOrder::FirstInS1 if strategy.scans_second(cs2_len, cs1_len) => {
+                return strategy.find_prefiltered(s2, s1, k, bloom).map(|m| m.swap());
+            },
+            _ => {},
+        }
+
+        // Each distinct common substring is returned at its first occurrence in both strings.
+        let matches = strategy.find_all_prefiltered(s1, s2, k, bloom).into_iter();
+        match order {
+            MatchOrder::Any => unreachable!(),
+            MatchOrder::FirstInS2 => matches.min_by_key(|m| m.s2_char_index),
+            MatchOrder::FirstInS1 => matches.min_by_key(|m| m.s1_char_index),
+            MatchOrder::SmallestMaxPosition => matches.min_by_key(|m| (std::cmp::max(m.s1_char_index, m.s2_char_index), m.s2_char_index)),
+            MatchOrder::Lexicographic => matches.min_by_key(|m| m.text),
+        }
+    }
+
+    // Runs the strategy, using `_bloom_prefiltered_shorter_substring()` with the strategy's hasher
+    // if given a Bloom filter config. Only the strategies that put the shorter string in a hash
+    // table keyed by their hasher support the filter, the others ignore it.
+    fn find_prefiltered<'a>(self, s1: &'a str, s2: &'a str, k: usize, bloom: Option<BloomFilterConfig>) -> Option<Match<'a>> {
+        let Some(config) = bloom else {
+            return self.find(s1, s2, k);
+        };
+        match self {
+            Strategy::FxShorter => implementations::_bloom_prefiltered_shorter_substring(s1, s2, k, config, |s| {
+                implementations::build_fx_substring(s, k)
+            }),
+            Strategy::RollingAdler => implementations::_bloom_prefiltered_shorter_substring(s1, s2, k, config, |s| {
+                implementations::build_rolling_adler_substring(s, k)
+            }),
+            Strategy::RollingPoly => {
+                // Both strings need to be hashed using the same salt
+                let salt = RollingPolynomial::new().salt();
+                implementations::_bloom_prefiltered_shorter_substring(s1, s2, k, config, |s| {
+                    implementations::build_rolling_polynomial_substring_with_salt(s, k, salt)
+                })
+            },
+            _ => self.find(s1, s2, k),
+        }
+    }
+
+    // Same as `find_prefiltered()`, but finds all the common substrings like `find_all()`.
+    fn find_all_prefiltered<'a>(self, s1: &'a str, s2: &'a str, k: usize, bloom: Option<BloomFilterConfig>) -> Vec<Match<'a>> {
+        if bloom.is_none() {
+            return self.find_all(s1, s2, k);
+        }
+        match self {
+            Strategy::FxShorter => implementations::all_shorter_substrings(s1, s2, k, bloom, |s| {
+                implementations::build_fx_substring(s, k)
+            }),
+            Strategy::RollingAdler => implementations::all_shorter_substrings(s1, s2, k, bloom, |s| {
+                implementations::build_rolling_adler_substring(s, k)
+            }),
+            Strategy::RollingPoly => {
+                // Both strings need to be hashed using the same salt
+                let salt = RollingPolynomial::new().salt();
+                implementations::all_shorter_substrings(s1, s2, k, bloom, |s| {
+                    implementations::build_rolling_polynomial_substring_with_salt(s, k, salt)
+                })
+            },
+            _ => self.find_all(s1, s2, k),
+        }
+    }
+
+    // Returns true if the strategy always returns the first match in the second string, given the
+    // lengths (in characters) of the first and second strings.
+    fn scans_second(self, first_len: usize, second_len: usize) -> bool {
+        match self {
+            Strategy::Naive | Strategy::NaivePrereserve | Strategy::NaivePrereserveIter | Strategy::Fx => true,
+            // These put the shorter string in the hash table (the first string if the lengths are
+            // the same) and scan the other one.
+            Strategy::FxShorter | Strategy::RollingAdler | Strategy::RollingPoly | Strategy::SuffixAutomaton
+                | Strategy::Fingerprint | Strategy::Fingerprint128 | Strategy::PackedDna => first_len <= second_len,
+            Strategy::Alternate | Strategy::Auto => false,
+        }
+    }
+
+    // Returns the function that implements the strategy.
+    fn implementation(self) -> Implementation {
+        match self {
+            Strategy::Naive => implementations::_naive_substring,
+            Strategy::NaivePrereserve => implementations::_naive_prereserve_substring,
+            Strategy::NaivePrereserveIter => implementations::_naive_prereserve_iter_substring,
+            Strategy::Fx => implementations::_naive_prereserve_iter_fx_substring,
+            Strategy::FxShorter => implementations::_naive_prereserve_iter_fx_shorter_substring,
+            Strategy::RollingAdler => implementations::_naive_prereserve_iter_rolling_adler_shorter_substring,
+            Strategy::RollingPoly => implementations::_naive_prereserve_iter_rolling_poly_shorter_substring,
+            Strategy::Alternate => implementations::_alternate_prereserve_iter_fx_substring,
+            Strategy::SuffixAutomaton => implementations::_suffix_automaton_shorter_substring,
+            Strategy::Fingerprint => implementations::_fingerprint_rolling_poly_shorter_substring,
+            Strategy::Fingerprint128 => implementations::_fingerprint128_rolling_poly_shorter_substring,
+            Strategy::PackedDna => implementations::_dna_packed_shorter_substring,
+            // Auto needs to look at the inputs to pick an implementation, see `find()`.
+            Strategy::Auto => panic!("Strategy::Auto has to be resolved before it is run"),
+        }
+    }
+}
+
+/// Common interface of the substring search implementations, so they can be used interchangeably
+/// (e.g. to loop over all of them in tests and benchmarks).
+pub trait SubstringFinder {
+    /// Given two strings, returns a common substring of length k or None if no such substring
+    /// exists.
+    fn find<'a>(&self, s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>>;
+
+    /// Given two strings, returns every distinct common substring of length k, in the order given
+    /// by `common_substrings(s1, s2, k).distinct()` (i.e. in the order they first occur in the
+    /// longer string, at their first occurrence in both strings).
+    fn find_all<'a>(&self, s1: &'a str, s2: &'a str, k: usize) -> Vec<Match<'a>>;
+
+    /// Returns the name of the implementation.
+    fn name(&self) -> &'static str;
+}
+
+impl SubstringFinder for Strategy {
+    fn find<'a>(&self, s1: &'a str, s2: &'a str, k: usize) -> Option<Match<'a>> {
+        self.resolve(s1, s2, k).implementation()(s1, s2, k)
+    }
+
+    /// Finds all the common substrings using the same hasher (or automaton, or packed k-mers) as
+    /// the strategy uses to find the first one. The strategies that don't put the shorter string in
+    /// the hash table still do so here, since every substring of the longer string has to be
+    /// checked anyway.
+    fn find_all<'a>(&self, s1: &'a str, s2: &'a str, k: usize) -> Vec<Match<'a>> {
+        match self.resolve(s1, s2, k) {
+            Strategy::Naive | Strategy::NaivePrereserve | Strategy::NaivePrereserveIter => {
+                implementations::all_shorter_substrings(s1, s2, k, None, |s| implementations::build_sip_substring(s, k))
+            },
+            Strategy::Fx | Strategy::FxShorter | Strategy::Alternate => {
+                implementations::all_shorter_substrings(s1, s2, k, None, |s| implementations::build_fx_substring(s, k))
+            },
+            Strategy::RollingAdler => {
+                implementations::all_shorter_substrings(s1, s2, k, None, |s| implementations::build_rolling_adler_substring(s, k))
+            },
+            Strategy::RollingPoly | Strategy::Fingerprint | Strategy::Fingerprint128 => {
+                // Both strings need to be hashed using the same salt
+                let salt = RollingPolynomial::new().salt();
+                implementations::all_shorter_substrings(s1, s2, k, None, |s| {
+                    implementations::build_rolling_polynomial_substring_with_salt(s, k, salt)
+                })
+            },
+            Strategy::SuffixAutomaton => {
+                // The automaton is built for the shorter string, like `_suffix_automaton_shorter_substring()`
+                if s1.chars().count() <= s2.chars().count() {
+                    SuffixAutomaton::new(s1).find_all(s2, k)
+                } else {
+                    SuffixAutomaton::new(s2).find_all(s1, k).into_iter().map(|m| m.swap()).collect()
+                }
+            },
+            Strategy::PackedDna => dna_common_substrings(s1, s2, k)
+                .unwrap_or_else(|_| Strategy::FxShorter.find_all(s1, s2, k)),
+            Strategy::Auto => unreachable!(),
+        }
+    }
+
+    /// Returns the name of the function that implements the strategy (without the leading
+    /// underscore). This is also the name used in the benchmarks.
+    fn name(&self) -> &'static str {
+        match self {
+            Strategy::Naive => "naive_substring",
+            Strategy::NaivePrereserve => "naive_prereserve_substring",
+            Strategy::NaivePrereserveIter => "naive_prereserve_iter_substring",
+            Strategy::Fx => "naive_prereserve_iter_fx_substring",
+            Strategy::FxShorter => "naive_prereserve_iter_fx_shorter_substring",
+            Strategy::RollingAdler => "naive_prereserve_iter_rolling_adler_shorter_substring",
+            Strategy::RollingPoly => "naive_prereserve_iter_rolling_poly_shorter_substring",
+            Strategy::Alternate => "alternate_prereserve_iter_fx_substring",
+            Strategy::SuffixAutomaton => "suffix_automaton_shorter_substring",
+            Strategy::Fingerprint => "fingerprint_rolling_poly_shorter_substring",
+            Strategy::Fingerprint128 => "fingerprint128_rolling_poly_shorter_substring",
+            Strategy::PackedDna => "dna_packed_shorter_substring",
+            Strategy::Auto => "auto",
+        }
+    }
+}
+
+/// Configurable common substring search. This is the supported way to pick which implementation
+/// is used, e.g. `SubstringSearch::new().strategy(Strategy::RollingPoly).k(20).run(s1, s2)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubstringSearch {
+    strategy: Strategy,
+    k: usize,
+    order: MatchOrder,
+    bloom_filter: Option<BloomFilterConfig>,
+    fingerprint_width: FingerprintWidth,
+}
+
+impl Default for SubstringSearch {
+    /// Searches for substrings of length 20 (the length used by the example binary and benchmarks)
+    /// using the same strategy as `substring()`.
+    fn default() -> Self {
+        SubstringSearch {
+            strategy: Strategy::default(),
+            k: 20,
+            order: MatchOrder::default(),
+            bloom_filter: None,
+            fingerprint_width: FingerprintWidth::default(),
+        }
+    }
+}
+
+impl SubstringSearch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the strategy used to search for a common substring.
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Sets the length (in characters) of the common substring to search for.
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    /// Sets which common substring is returned if there are several, see `MatchOrder`. Unless the
+    /// strategy naturally finds the first match in that order (see `Strategy::find_ordered()`),
+    /// all the common substrings are found with the strategy's `find_all()` first, which still
+    /// uses the Bloom filter if one is configured.
+    pub fn order(mut self, order: MatchOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Sets the config of the Bloom filter that is checked before probing the hash table, or None
+    /// to not use one (the default). Only `Strategy::FxShorter`, `Strategy::RollingAdler` and
+    /// `Strategy::RollingPoly` (including when picked by `Strategy::Auto`) use the filter. It only
+    /// affects performance, the same substring is found with or without it.
+    pub fn bloom_filter(mut self, bloom_filter: Option<BloomFilterConfig>) -> Self {
+        self.bloom_filter = bloom_filter;
+        self
+    }
+
+    /// Sets the width of the fingerprints stored by `Strategy::Fingerprint` (including when picked
+    /// by `Strategy::Auto`), 64 bits by default. Wider fingerprints take more memory but have fewer
+    /// false positives, each of which costs a search through the shorter string to verify. It only
+    /// affects performance, the same substring is found with either width.
+    /// `FingerprintWidth::Bits128` makes `Strategy::Fingerprint` behave like
+    /// `Strategy::Fingerprint128`.
+    pub fn fingerprint_width(mut self, fingerprint_width: FingerprintWidth) -> Self {
+        self.fingerprint_width = fingerprint_width;
+        self
+    }
+
+    /// Returns the strategy used to search for a common substring.
+    pub fn get_strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    /// Returns the length (in characters) of the common substring to search for.
+    pub fn get_k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns which common substring is returned if there are several.
+    pub fn get_order(&self) -> MatchOrder {
+        self.order
+    }
+
+    /// Returns the config of the Bloom filter that is checked before probing the hash table.
+    pub fn get_bloom_filter(&self) -> Option<BloomFilterConfig> {
+        self.bloom_filter
+    }
+
+    /// Returns the width of the fingerprints stored by `Strategy::Fingerprint`.
+    pub fn get_fingerprint_width(&self) -> FingerprintWidth {
+        self.fingerprint_width
+    }
+
+    /// Returns the strategy that `run()` uses for the given inputs. This differs from
+    /// `get_strategy()` when using `Strategy::Auto`, in which case it returns the strategy that was
+    /// picked, and when `Strategy::Fingerprint` uses 128-bit fingerprints, in which case it returns
+    /// `Strategy::Fingerprint128`.
+    pub fn chosen_strategy(&self, s1: &str, s2: &str) -> Strategy {
+        match (self.strategy.resolve(s1, s2, self.k), self.fingerprint_width) {
+            (Strategy::Fingerprint, FingerprintWidth::Bits128) => Strategy::Fingerprint128,
+            (strategy, _) => strategy,
+        }
+    }
+
+    /// Given two strings, returns a common substring of length k (the first one in the configured
+    /// order) or None if no such substring exists.
+    pub fn run<'a>(&self, s1: &'a str, s2: &'a str) -> Option<Match<'a>> {
+        self.chosen_strategy(s1, s2).find_filtered(s1, s2, self.k, self.order, self.bloom_filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use crate::implementations::*;
+    use crate::bloom::BloomFilterConfig;
+    use crate::search::{alphabet_size, FingerprintWidth, MatchOrder, Strategy, SubstringFinder, SubstringSearch, AUTO_LARGE_INPUT_BYTES, AUTO_TINY_INPUT_BYTES};
+    use crate::{common_substrings, substring};
+
+    const ORDERS: [MatchOrder; 4] = [MatchOrder::FirstInS2, MatchOrder::FirstInS1, MatchOrder::SmallestMaxPosition, MatchOrder::Lexicographic];
+
+    // Reference implementation for ordered substring search to compare against for correctness.
+    // Unlike `substring()`, the result doesn't depend on which string is shorter.
+    fn find_ordered_reference_impl<'a>(s1: &'a str, s2: &'a str, k: usize, order: MatchOrder) -> Option<Match<'a>> {
+        // Returns the (byte offset, char index) of the first occurrence of each k-length substring.
+        fn first_occurrences(s: &str, k: usize) -> Vec<(&str, (usize, usize))> {
+            let cis: Vec<usize> = s.char_indices().map(|(i, _)| i).chain(std::iter::once(s.len())).collect();
+            let mut occurrences: Vec<(&str, (usize, usize))> = Vec::new();
+            for i in 0..(cis.len().saturating_sub(k)) {
+                let sub = &s[cis[i]..cis[i+k]];
+                if !occurrences.iter().any(|(other, _)| *other == sub) {
+                    occurrences.push((sub, (cis[i], i)));
+                }
+            }
+            occurrences
+        }
+
+        let occurrences1 = first_occurrences(s1, k);
+        let matches = first_occurrences(s2, k).into_iter().filter_map(|(sub, s2_start)| {
+            occurrences1.iter().find(|(other, _)| *other == sub).map(|(_, s1_start)| Match::new(sub, *s1_start, s2_start))
+        });
+        match order {
+            MatchOrder::Any => unreachable!(),
+            MatchOrder::FirstInS2 => matches.min_by_key(|m| m.s2_char_index),
+            MatchOrder::FirstInS1 => matches.min_by_key(|m| m.s1_char_index),
+            MatchOrder::SmallestMaxPosition => matches.min_by_key(|m| (std::cmp::max(m.s1_char_index, m.s2_char_index), m.s2_char_index)),
+            MatchOrder::Lexicographic => matches.min_by_key(|m| m.text),
+        }
+    }
+
+    #[test]
+    fn test_search_strategies() {
+        let s1 = "This is a test string. - Normal Person";
+        let s2 = "Here be another test string. Yaargh. - Pirate";
+        let k = 5;
+
+        assert_eq!(SubstringSearch::new().strategy(Strategy::Naive).k(k).run(s1, s2), _naive_substring(s1, s2, k));
+        assert_eq!(SubstringSearch::new().strategy(Strategy::NaivePrereserve).k(k).run(s1, s2), _naive_prereserve_substring(s1, s2, k));
+        assert_eq!(SubstringSearch::new().strategy(Strategy::NaivePrereserveIter).k(k).run(s1, s2), _naive_prereserve_iter_substring(s1, s2, k));
+        assert_eq!(SubstringSearch::new().strategy(Strategy::Fx).k(k).run(s1, s2), _naive_prereserve_iter_fx_substring(s1, s2, k));
+        assert_eq!(SubstringSearch::new().strategy(Strategy::FxShorter).k(k).run(s1, s2), _naive_prereserve_iter_fx_shorter_substring(s1, s2, k));
+        assert_eq!(SubstringSearch::new().strategy(Strategy::RollingAdler).k(k).run(s1, s2), _naive_prereserve_iter_rolling_adler_shorter_substring(s1, s2, k));
+        assert_eq!(SubstringSearch::new().strategy(Strategy::RollingPoly).k(k).run(s1, s2), _naive_prereserve_iter_rolling_poly_shorter_substring(s1, s2, k));
+        assert_eq!(SubstringSearch::new().strategy(Strategy::Alternate).k(k).run(s1, s2), _alternate_prereserve_iter_fx_substring(s1, s2, k));
+        assert_eq!(SubstringSearch::new().strategy(Strategy::SuffixAutomaton).k(k).run(s1, s2), _suffix_automaton_shorter_substring(s1, s2, k));
+        assert_eq!(SubstringSearch::new().strategy(Strategy::Fingerprint).k(k).run(s1, s2), _fingerprint_rolling_poly_shorter_substring(s1, s2, k));
+        assert_eq!(SubstringSearch::new().strategy(Strategy::Fingerprint128).k(k).run(s1, s2), _fingerprint128_rolling_poly_shorter_substring(s1, s2, k));
+        assert_eq!(SubstringSearch::new().strategy(Strategy::PackedDna).k(k).run(s1, s2), _dna_packed_shorter_substring(s1, s2, k));
+    }
+
+    #[test]
+    fn test_search_defaults() {
+        let s1 = "This is a test string. - Normal Person";
+        let s2 = "Here be another test string. Yaargh. - Pirate";
+        let search = SubstringSearch::new();
+
+        assert_eq!(search.get_strategy(), Strategy::FxShorter);
+        assert_eq!(search.get_k(), 20);
+        assert_eq!(search.get_bloom_filter(), None);
+        assert_eq!(search.get_fingerprint_width(), FingerprintWidth::Bits64);
+        assert_eq!(search.clone().k(5).run(s1, s2), substring(s1, s2, 5));
+        assert_eq!(search.run(s1, s2), None);
+    }
+
+    #[test]
+    fn test_search_fingerprint_width() {
+        let s1 = "This is a test string. - Normal Person";
+        let s2 = "Here be another test string. Yaargh. - Pirate";
+        let search = SubstringSearch::new().strategy(Strategy::Fingerprint).k(5);
+
+        assert_eq!(search.chosen_strategy(s1, s2), Strategy::Fingerprint);
+        let wide = search.clone().fingerprint_width(FingerprintWidth::Bits128);
+        assert_eq!(wide.get_fingerprint_width(), FingerprintWidth::Bits128);
+        assert_eq!(wide.chosen_strategy(s1, s2), Strategy::Fingerprint128);
+        assert_eq!(wide.run(s1, s2), search.run(s1, s2));
+        assert_eq!(wide.run(s1, s2), substring(s1, s2, 5));
+        // Only the fingerprint strategies are affected.
+        let fx = wide.strategy(Strategy::FxShorter);
+        assert_eq!(fx.chosen_strategy(s1, s2), Strategy::FxShorter);
+    }
+
+    #[test]
+    fn test_bloom_filter_search() {
+        let s1 = "›It costs €10 for this item… or €12 for that one";
+        let s2 = "Pay€10 now";
+        let config = BloomFilterConfig::new().bits_per_item(2);
+
+        for strategy in Strategy::ALL {
+            for order in ORDERS {
+                let search = SubstringSearch::new().strategy(strategy).k(4).order(order);
+                assert_eq!(search.clone().bloom_filter(Some(config)).run(s1, s2), search.run(s1, s2));
+            }
+        }
+    }
+
+    #[test]
+    fn test_finders() {
+        let s1 = "abcabc";
+        let s2 = "xabcxbca";
+        let k = 3;
+
+        let finders: Vec<&dyn SubstringFinder> = Strategy::ALL.iter().map(|s| s as &dyn SubstringFinder).collect();
+        for finder in finders {
+            assert_eq!(finder.find(s1, s2, k).unwrap().char_len(), k);
+            assert_eq!(finder.find_all(s1, s2, k), common_substrings(s1, s2, k).distinct().collect::<Vec<_>>());
+            assert_eq!(finder.find_all(s1, s2, k).iter().map(|m| m.text).collect::<Vec<_>>(), vec!["abc", "bca"]);
+        }
+
+        // Names are used to identify the implementations (e.g. in benchmarks) so must be unique.
+        let mut names: Vec<&str> = Strategy::ALL.iter().map(|s| s.name()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), Strategy::ALL.len());
+    }
+
+    #[test]
+    fn test_alphabet_size() {
+        assert_eq!(alphabet_size("", 0.95), 0);
+        assert_eq!(alphabet_size("GATTACA", 0.95), 4);
+        // The rare characters shouldn't count towards the alphabet
+        let genome = format!(">header line{}", "GATTACA".repeat(100));
+        assert_eq!(alphabet_size(&genome, 0.95), 4);
+        assert!(alphabet_size("This is a test string. - Normal Person", 0.95) > 8);
+    }
+
+    #[test]
+    fn test_auto_strategy() {
+        let prose1 = "It was the best of times, it was the worst of times, it was the age of wisdom. ".repeat(10);
+        let prose2 = "Call me Ishmael. Some years ago, never mind how long precisely, having little money. ".repeat(10);
+        let dna1 = "GATTACAGGCTTACCGATAGCTAG".repeat(10);
+        let dna2 = "TTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTT";
+
+        // Common substring found early on
+        assert_eq!(Strategy::Auto.resolve(&prose1, &prose1, 20), Strategy::Alternate);
+        // Limited vocabulary
+        assert_eq!(Strategy::Auto.resolve(&dna1, dna2, 50), Strategy::FxShorter);
+        assert_eq!(Strategy::Auto.resolve(&format!(">header\n{}", dna1), dna2, 20), Strategy::FxShorter);
+        // DNA sequences with k-mers that fit in 64 bits, including unknown bases
+        assert_eq!(Strategy::Auto.resolve(&dna1, dna2, 20), Strategy::PackedDna);
+        assert_eq!(Strategy::Auto.resolve(&format!("NNNN{}", dna1), dna2, 32), Strategy::PackedDna);
+        assert_eq!(Strategy::Auto.resolve(&dna1, dna2, 33), Strategy::FxShorter);
+        // Small k
+        assert_eq!(Strategy::Auto.resolve(&prose1, &prose2, 10), Strategy::FxShorter);
+        // Large k
+        assert_eq!(Strategy::Auto.resolve(&prose1, &prose2, 50), Strategy::RollingPoly);
+        assert_eq!(SubstringSearch::new().strategy(Strategy::Auto).k(50).chosen_strategy(&prose1, &prose2), Strategy::RollingPoly);
+
+        // Tiny inputs, even with a common substring early on
+        assert_eq!(Strategy::Auto.resolve("abcdef", "abcdef", 3), Strategy::Naive);
+        let short1 = &prose1[..AUTO_TINY_INPUT_BYTES / 2];
+        assert_eq!(Strategy::Auto.resolve(short1, &prose2[..AUTO_TINY_INPUT_BYTES / 2], 10), Strategy::Naive);
+        assert_eq!(Strategy::Auto.resolve(short1, &prose2[..AUTO_TINY_INPUT_BYTES / 2 + 1], 10), Strategy::FxShorter);
+
+        // Very large inputs, unless a common substring is found early on. Just below the threshold
+        // the DNA-like alphabet picks the strategy instead.
+        let large1 = "a".repeat(AUTO_LARGE_INPUT_BYTES / 2);
+        let large2 = "b".repeat(AUTO_LARGE_INPUT_BYTES / 2);
+        assert_eq!(Strategy::Auto.resolve(&large1, &large2, 50), Strategy::Fingerprint);
+        assert_eq!(Strategy::Auto.resolve(&large1, &large2[1..], 50), Strategy::FxShorter);
+        assert_eq!(Strategy::Auto.resolve(&large1, &large1, 50), Strategy::Alternate);
+
+        // Other strategies always resolve to themselves
+        for strategy in Strategy::ALL {
+            assert_eq!(strategy.resolve(&prose1, &prose1, 20), strategy);
+        }
+    }
+
+    #[test]
+    fn test_auto_search() {
+        let s1 = "This is a test string. - Normal Person";
+        let s2 = "Here be another test string. Yaargh. - Pirate";
+        let s3 = "Who lives in a pineapple under the sea? - Patchy";
+
+        assert_eq!(Strategy::Auto.find(s1, s2, 5).map(|m| m.text), Some(" test"));
+        assert_eq!(Strategy::Auto.find(s1, s3, 5), None);
+        assert_eq!(SubstringSearch::new().strategy(Strategy::Auto).k(5).run(s1, s2).map(|m| m.text), Some(" test"));
+    }
+
+    #[test]
+    fn test_match_order() {
+        let s1 = "xxcdyyabzz";
+        let s2 = "abqqcd";
+        let k = 2;
+
+        // The shorter-first strategies would normally scan s1 since it's the longer string.
+        for strategy in Strategy::ALL {
+            let search = SubstringSearch::new().strategy(strategy).k(k);
+            assert_eq!(search.clone().order(MatchOrder::FirstInS2).run(s1, s2).map(|m| m.text), Some("ab"));
+            assert_eq!(search.clone().order(MatchOrder::FirstInS1).run(s1, s2).map(|m| m.text), Some("cd"));
+            assert_eq!(search.clone().order(MatchOrder::SmallestMaxPosition).run(s1, s2).map(|m| m.text), Some("cd"));
+            assert_eq!(search.clone().order(MatchOrder::Lexicographic).run(s1, s2).map(|m| m.text), Some("ab"));
+        }
+        assert_eq!(SubstringSearch::new().get_order(), MatchOrder::Any);
+    }
+
+    proptest! {
+        #[test]
+        // Tests that every strategy returns matches in the requested order, no matter which string
+        // is shorter. A tiny Bloom filter has plenty of false positives, which must not change the
+        // result either.
+        fn test_match_order_against_reference(
+            s1 in "[abc€]{0,20}",
+            s2 in "[abc€]{0,20}",
+            k in 1..5usize,
+        ) {
+            let config = BloomFilterConfig::new().bits_per_item(1).num_hashes(1);
+            for order in ORDERS {
+                let expected = find_ordered_reference_impl(&s1, &s2, k, order);
+                for strategy in Strategy::ALL.into_iter().chain([Strategy::Auto]) {
+                    assert_eq!(strategy.find_ordered(&s1, &s2, k, order), expected.clone());
+                    let search = SubstringSearch::new().strategy(strategy).k(k).order(order).bloom_filter(Some(config));
+                    assert_eq!(search.run(&s1, &s2), expected.clone());
+                }
+            }
+        }
+
+        #[test]
+        // Each strategy finds all the common substrings its own way, but they should all be the same
+        // as the ones `common_substrings()` finds. DNA bases (including unknown Ns) let the packed strategy
+        // use its k-mers.
+        fn test_find_all_against_common_substrings(
+            s1 in "[ACGTN€]{0,20}",
+            s2 in "[ACGTN€]{0,20}",
+            k in 0..5usize,
+        ) {
+            let expected: Vec<_> = common_substrings(&s1, &s2, k).distinct().collect();
+            for strategy in Strategy::ALL.into_iter().chain([Strategy::Auto]) {
+                assert_eq!(strategy.find_all(&s1, &s2, k), expected.clone(), "{}", strategy.name());
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+use std::hash::Hash;
+use crate::implementations::{fx_shorter_window, fx_window};
+use crate::search::Strategy;
+
+/// A window of k items found in two sequences, along with where it starts in each of them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WindowMatch<'a, T> {
+    /// The common window.
+    pub items: &'a [T],
+    /// Index at which the window starts in s1.
+    pub s1_index: usize,
+    /// Index at which the window starts in s2.
+    pub s2_index: usize,
+}
+
+/// Reasons two sequences can't be searched with the requested strategy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SequenceError {
+    /// The strategy only works on strings (e.g. it hashes the bytes of each window or builds a
+    /// suffix automaton over characters). Only `Strategy::FxShorter`, `Strategy::Fx` and
+    /// `Strategy::Auto` (which picks `Strategy::FxShorter`) can search sequences.
+    UnsupportedStrategy(Strategy),
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SequenceError::UnsupportedStrategy(strategy) => {
+                write!(f, "strategy {:?} can only search strings, not sequences", strategy)
+            },
+        }
+    }
+}
+
+impl std::error::Error for SequenceError {}
+
+/// Given two sequences, returns the first found common window of k items or None if no such
+/// window exists. This works for any sequence of hashable items, like pre-tokenized text (e.g.
+/// `&[u32]` word IDs).
+///
+/// This function uses the same hash table and scan as `substring()` (i.e. `Strategy::FxShorter`),
+/// which work on the windows of any sequence. It sticks all the k-length windows of the shorter
+/// sequence in a pre-reserved hash map (using the firefox hashing algorithm), then checks the
+/// windows of the other sequence in order to see if any are already in the hash map. Use
+/// `common_subsequence_window_with()` to pick another strategy.
+pub fn common_subsequence_window<'a, T: Hash + Eq>(s1: &'a [T], s2: &'a [T], k: usize) -> Option<WindowMatch<'a, T>> {
+    common_subsequence_window_with(s1, s2, k, Strategy::FxShorter).unwrap()
+}
+
+/// Same as `common_subsequence_window()`, but searches the sequences with the given strategy. The
+/// strategies share their hash tables with the string search, so the same window is found as when
+/// searching strings with that strategy (e.g. `Strategy::Fx` returns the first common window in
+/// s2).
+///
+/// Only the strategies that hash whole windows can search sequences: `Strategy::FxShorter`,
+/// `Strategy::Fx` and `Strategy::Auto` (which always picks `Strategy::FxShorter` for sequences).
+/// The others return `SequenceError::UnsupportedStrategy`.
+pub fn common_subsequence_window_with<'a, T: Hash + Eq>(s1: &'a [T], s2: &'a [T], k: usize, strategy: Strategy) -> Result<Option<WindowMatch<'a, T>>, SequenceError> {
+    let find = match strategy {
+        Strategy::FxShorter | Strategy::Auto => fx_shorter_window,
+        Strategy::Fx => fx_window,
+        _ => return Err(SequenceError::UnsupportedStrategy(strategy)),
+    };
+
+    // Trivial to have matching windows of length 0
+    if k == 0 {
+        return Ok(Some(WindowMatch { items: &s1[..0], s1_index: 0, s2_index: 0 }));
+    }
+
+    // Offsets and indices are the same for slices
+    Ok(find(s1, s2, k).map(|(items, (s1_index, _), (s2_index, _))| WindowMatch { items, s1_index, s2_index }))
+}
+
+/// Given two byte slices, returns the first found common run of k bytes or None if no such run
+/// exists. Unlike `substring()`, the inputs don't need to be valid UTF-8 and k counts bytes rather
+/// than characters, which makes it suitable for searching binary files.
+pub fn substring_bytes<'a>(s1: &'a [u8], s2: &'a [u8], k: usize) -> Option<WindowMatch<'a, u8>> {
+    common_subsequence_window(s1, s2, k)
+}
+
+/// Same as `substring_bytes()`, but searches the bytes with the given strategy. See
+/// `common_subsequence_window_with()` for the strategies that are supported.
+pub fn substring_bytes_with<'a>(s1: &'a [u8], s2: &'a [u8], k: usize, strategy: Strategy) -> Result<Option<WindowMatch<'a, u8>>, SequenceError> {
+    common_subsequence_window_with(s1, s2, k, strategy)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use crate::sequences::{common_subsequence_window, common_subsequence_window_with, substring_bytes, substring_bytes_with, SequenceError, WindowMatch};
+    use crate::{substring, Strategy, SubstringSearch};
+
+    #[test]
+    fn test_substring_bytes() {
+        // Not valid UTF-8
+        let s1: &[u8] = &[0xff, 0x00, 0xfe, 0x01, 0x02, 0x03, 0xc0];
+        let s2: &[u8] = &[0x02, 0x03, 0xff, 0xfe, 0x01, 0x02, 0x03, 0x04];
+
+        let expected = WindowMatch { items: &s1[2..5], s1_index: 2, s2_index: 3 };
+        assert_eq!(substring_bytes(s1, s2, 3), Some(expected));
+        assert_eq!(substring_bytes(s1, s2, 5), None);
+        assert_eq!(substring_bytes(s1, &[], 1), None);
+    }
+
+    #[test]
+    fn test_common_subsequence_window_tokens() {
+        // Word IDs of two tokenized documents
+        let s1: &[u32] = &[7, 42, 3, 3, 19, 8, 100];
+        let s2: &[u32] = &[1, 2, 3, 3, 19, 5];
+
+        let r = common_subsequence_window(s1, s2, 3).unwrap();
+        assert_eq!(r.items, &[3, 3, 19]);
+        assert_eq!(r.s1_index, 2);
+        assert_eq!(r.s2_index, 2);
+        assert_eq!(common_subsequence_window(s1, s2, 4), None);
+        assert_eq!(common_subsequence_window(s1, s2, 0).map(|m| m.items.len()), Some(0));
+    }
+
+    #[test]
+    fn test_sequence_strategies() {
+        let s1: &[u8] = b"xxabcyyabd";
+        let s2: &[u8] = b"abdzzabc";
+
+        // FxShorter returns the first window of the longer sequence, Fx the first one of s2
+        let first_in_s1 = WindowMatch { items: &s1[2..5], s1_index: 2, s2_index: 5 };
+        let first_in_s2 = WindowMatch { items: &s1[7..10], s1_index: 7, s2_index: 0 };
+        assert_eq!(substring_bytes_with(s1, s2, 3, Strategy::FxShorter), Ok(Some(first_in_s1.clone())));
+        assert_eq!(substring_bytes_with(s1, s2, 3, Strategy::Auto), Ok(Some(first_in_s1)));
+        assert_eq!(substring_bytes_with(s1, s2, 3, Strategy::Fx), Ok(Some(first_in_s2)));
+        assert_eq!(substring_bytes_with(s1, s2, 4, Strategy::Fx), Ok(None));
+        assert_eq!(substring_bytes_with(s1, s2, 0, Strategy::Fx).map(|m| m.map(|m| m.items.len())), Ok(Some(0)));
+
+        for strategy in [Strategy::Naive, Strategy::RollingPoly, Strategy::SuffixAutomaton, Strategy::PackedDna] {
+            assert_eq!(substring_bytes_with(s1, s2, 3, strategy), Err(SequenceError::UnsupportedStrategy(strategy)));
+            assert_eq!(common_subsequence_window_with(s1, s2, 0, strategy), Err(SequenceError::UnsupportedStrategy(strategy)));
+        }
+        assert_eq!(
+            SequenceError::UnsupportedStrategy(Strategy::Naive).to_string(),
+            "strategy Naive can only search strings, not sequences",
+        );
+    }
+
+    proptest! {
+        #[test]
+        // For ASCII strings, searching the bytes should behave exactly like searching the string.
+        fn test_substring_bytes_against_substring(
+            s1 in "[abc]{0,20}",
+            s2 in "[abc]{0,20}",
+            k in 1..6usize,
+        ) {
+            let expected = substring(&s1, &s2, k)
+                .map(|m| (m.text.as_bytes(), m.s1_range.start, m.s2_range.start));
+            let r = substring_bytes(s1.as_bytes(), s2.as_bytes(), k)
+                .map(|m| (m.items, m.s1_index, m.s2_index));
+            assert_eq!(r, expected);
+        }
+
+        #[test]
+        // Searching the characters of two strings as a token sequence should find the same window
+        // as searching the strings, including when they have multi-byte characters.
+        fn test_common_subsequence_window_against_substring(
+            s1 in "[ab€]{0,20}",
+            s2 in "[ab€]{0,20}",
+            k in 1..6usize,
+        ) {
+            let (c1, c2): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+            let expected = substring(&s1, &s2, k)
+                .map(|m| (m.text.chars().collect::<Vec<_>>(), m.s1_char_index, m.s2_char_index));
+            let r = common_subsequence_window(&c1, &c2, k)
+                .map(|m| (m.items.to_vec(), m.s1_index, m.s2_index));
+            assert_eq!(r, expected);
+        }
+
+        #[test]
+        // Each supported strategy should find the same window in the bytes as in the string.
+        fn test_substring_bytes_with_against_search(
+            s1 in "[abc]{0,20}",
+            s2 in "[abc]{0,20}",
+            k in 1..6usize,
+        ) {
+            for strategy in [Strategy::FxShorter, Strategy::Fx] {
+                let expected = SubstringSearch::new().strategy(strategy).k(k).run(&s1, &s2)
+                    .map(|m| (m.text.as_bytes(), m.s1_range.start, m.s2_range.start));
+                let r = substring_bytes_with(s1.as_bytes(), s2.as_bytes(), k, strategy).unwrap()
+                    .map(|m| (m.items, m.s1_index, m.s2_index));
+                assert_eq!(r, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,498 @@
+use crate::implementations::{build_fx_positions_table, Match};
+
+// Separates s1 from s2 in the concatenated text. It's one past the largest unicode scalar value so
+// it can't occur in either string.
+const SEPARATOR: u32 = 0x110000;
+
+/// Generalized suffix array over two strings, along with its LCP (longest common prefix) array.
+/// The suffix array is built over `s1 + separator + s2`, so common substrings of s1 and s2 show up
+/// as common prefixes of adjacent suffixes that start in different strings. Since the arrays don't
+/// depend on k, a single build answers queries for every k (as well as for the longest common
+/// substring and the maximal shared substrings).
+///
+/// The arrays are built over characters (unicode scalar values) rather than bytes, so the matches
+/// are always on char boundaries like the ones returned by `substring()`. Building the suffix array
+/// takes ~O(n log^2(n)) time (using prefix doubling) and the LCP array takes ~O(n) time (using
+/// Kasai's algorithm), where n is the combined length of the strings.
+pub struct GeneralizedSuffixArray<'a> {
+    s1: &'a str,
+    s2: &'a str,
+    // Byte offset of each character in s1 and s2
+    s1_offsets: Vec<usize>,
+    s2_offsets: Vec<usize>,
+    // Characters of s1 + separator + s2
+    text: Vec<u32>,
+    // Start of each suffix of the text, in lexicographic order
+    suffixes: Vec<usize>,
+    // Length of the longest common prefix of each suffix and the one before it (0 for the first
+    // suffix)
+    lcp: Vec<usize>,
+}
+
+// Sorts the suffixes of the text using prefix doubling. After each round, the suffixes are sorted
+// by their first 2k characters.
+fn build_suffix_array(text: &[u32]) -> Vec<usize> {
+    let n = text.len();
+    let mut suffixes: Vec<usize> = (0..n).collect();
+    let mut rank: Vec<usize> = text.iter().map(|&c| c as usize).collect();
+    let mut next_rank = vec![0; n];
+
+    // Suffixes that are shorter than i + k characters sort before the ones that aren't
+    fn key(rank: &[usize], i: usize, k: usize) -> (usize, usize) {
+        (rank[i], rank.get(i + k).map_or(0, |r| r + 1))
+    }
+
+    // The text always holds at least the separator, so it's never empty
+    let mut k = 1;
+    loop {
+        suffixes.sort_unstable_by_key(|&i| key(&rank, i, k));
+        next_rank[suffixes[0]] = 0;
+        for w in suffixes.windows(2) {
+            next_rank[w[1]] = next_rank[w[0]] + (key(&rank, w[0], k) < key(&rank, w[1], k)) as usize;
+        }
+        std::mem::swap(&mut rank, &mut next_rank);
+
+        // Done once every suffix has a distinct rank
+        if rank[suffixes[n - 1]] == n - 1 {
+            break;
+        }
+        k *= 2;
+    }
+    suffixes
+}
+
+// Computes the LCP array using Kasai's algorithm
+fn build_lcp_array(text: &[u32], suffixes: &[usize]) -> Vec<usize> {
+    let n = text.len();
+    let mut ranks = vec![0; n];
+    for (r, &i) in suffixes.iter().enumerate() {
+        ranks[i] = r;
+    }
+
+    let mut lcp = vec![0; n];
+    // The common prefix shrinks by at most one character when moving on to the next suffix
+    let mut h: usize = 0;
+    for i in 0..n {
+        if ranks[i] == 0 {
+            h = 0;
+            continue;
+        }
+        let j = suffixes[ranks[i] - 1];
+        while i + h < n && j + h < n && text[i + h] == text[j + h] {
+            h += 1;
+        }
+        lcp[ranks[i]] = h;
+        h = h.saturating_sub(1);
+    }
+    lcp
+}
+
+fn char_offsets(s: &str) -> Vec<usize> {
+    s.char_indices().map(|(i, _)| i).chain([s.len()]).collect()
+}
+
+impl<'a> GeneralizedSuffixArray<'a> {
+    /// Builds the generalized suffix array and LCP array of s1 and s2.
+    pub fn new(s1: &'a str, s2: &'a str) -> Self {
+        let text: Vec<u32> = s1.chars().map(|c| c as u32)
+            .chain([SEPARATOR])
+            .chain(s2.chars().map(|c| c as u32))
+            .collect();
+        let suffixes = build_suffix_array(&text);
+        let lcp = build_lcp_array(&text, &suffixes);
+
+        GeneralizedSuffixArray {
+            s1,
+            s2,
+            s1_offsets: char_offsets(s1),
+            s2_offsets: char_offsets(s2),
+            text,
+            suffixes,
+            lcp,
+        }
+    }
+
+    // Number of characters in s1 (which is also the position of the separator in the text)
+    fn s1_chars(&self) -> usize {
+        self.s1_offsets.len() - 1
+    }
+
+    // Returns the char index of the suffix in s1 or s2 (as Ok or Err respectively)
+    fn locate(&self, suffix: usize) -> Result<usize, usize> {
+        let separator = self.s1_chars();
+        if suffix < separator {
+            Ok(suffix)
+        } else {
+            Err(suffix - separator - 1)
+        }
+    }
+
+    // Returns the character before the suffix, or None if the suffix starts one of the strings
+    fn left_char(&self, suffix: usize) -> Option<u32> {
+        if suffix == 0 || suffix == self.s1_chars() + 1 {
+            None
+        } else {
+            Some(self.text[suffix - 1])
+        }
+    }
+
+    // Builds the match of length `len` between the suffix starting at char index i in s1 and the
+    // suffix starting at char index j in s2
+    fn build_match(&self, i: usize, j: usize, len: usize) -> Match<'a> {
+        let s1_range = self.s1_offsets[i]..self.s1_offsets[i + len];
+        Match::new(&self.s1[s1_range], (self.s1_offsets[i], i), (self.s2_offsets[j], j))
+    }
+
+    // Returns the match between two suffixes that start in different strings, or None if they start
+    // in the same string (or one of them is the separator)
+    fn cross_match(&self, a: usize, b: usize, len: usize) -> Option<Match<'a>> {
+        let separator = self.s1_chars();
+        if a == separator || b == separator {
+            return None;
+        }
+        match (self.locate(a), self.locate(b)) {
+            (Ok(i), Err(j)) | (Err(j), Ok(i)) => Some(self.build_match(i, j, len)),
+            _ => None,
+        }
+    }
+
+    /// Returns the s1 this suffix array was built for.
+    pub fn s1(&self) -> &'a str {
+        self.s1
+    }
+
+    /// Returns the s2 this suffix array was built for.
+    pub fn s2(&self) -> &'a str {
+        self.s2
+    }
+
+    /// Returns a substring of length k that s1 and s2 have in common, or None if there is no such
+    /// substring. When there are several, the lexicographically smallest one is returned (at the
+    /// positions of an arbitrary pair of its occurrences).
+    pub fn common_substring(&self, k: usize) -> Option<Match<'a>> {
+        // Trivial to have matching substrings of length 0
+        if k == 0 {
+            return Some(Match::empty());
+        }
+
+        // All the suffixes that start with a common substring are adjacent in the suffix array, so
+        // at least one adjacent pair will start in different strings.
+        (1..self.suffixes.len())
+            .filter(|&r| self.lcp[r] >= k)
+            .find_map(|r| self.cross_match(self.suffixes[r - 1], self.suffixes[r], k))
+    }
+
+    /// Returns if s1 and s2 have a substring of length k in common.
+    pub fn has_common_substring(&self, k: usize) -> bool {
+        self.common_substring(k).is_some()
+    }
+
+    /// Returns the longest substring that s1 and s2 have in common, or None if they don't have any
+    /// characters in common. When there are several, the lexicographically smallest one is
+    /// returned.
+    pub fn longest_common_substring(&self) -> Option<Match<'a>> {
+        let mut longest: Option<Match<'a>> = None;
+        for r in 1..self.suffixes.len() {
+            if self.lcp[r] > longest.as_ref().map_or(0, |m| m.char_len()) {
+                if let Some(m) = self.cross_match(self.suffixes[r - 1], self.suffixes[r], self.lcp[r]) {
+                    longest = Some(m);
+                }
+            }
+        }
+        longest
+    }
+
+    /// Returns all the maximal shared substrings of s1 and s2 that are at least `min_len`
+    /// characters long. A maximal shared substring (also known as a maximal exact match) is a pair
+    /// of occurrences of the same substring in s1 and s2 that can't be extended to the left or to
+    /// the right. The matches are sorted by their position in s1 and then s2.
+    ///
+    /// Note that the number of maximal shared substrings can be quadratic in the length of the
+    /// strings when they are very repetitive.
+    pub fn maximal_substrings(&self, min_len: usize) -> Vec<Match<'a>> {
+        // There are no meaningful matches of length 0
+        let min_len = min_len.max(1);
+        let mut matches = Vec::new();
+        if self.suffixes.is_empty() {
+            return matches;
+        }
+
+        // Traverses the lcp-intervals bottom-up. An lcp-interval is a range of suffixes that share a
+        // common prefix of length lcp, and whose children are the (lcp-interval or single suffix)
+        // subranges that share longer prefixes. Pairs of suffixes from different children share
+        // exactly lcp characters, so their common prefix can't be extended to the right.
+        //
+        // Each stack entry holds the lcp of an interval and the suffixes of each of its children
+        // found so far.
+        let mut stack: Vec<(usize, Vec<Vec<usize>>)> = vec![(0, Vec::new())];
+        let mut last = vec![self.suffixes[0]];
+        for r in 1..=self.suffixes.len() {
+            let lcp = self.lcp.get(r).copied().unwrap_or(0);
+            while stack.last().unwrap().0 > lcp {
+                let (interval_lcp, mut children) = stack.pop().unwrap();
+                children.push(std::mem::take(&mut last));
+                if interval_lcp >= min_len {
+                    self.report_maximal(interval_lcp, &children, &mut matches);
+                }
+                last = children.concat();
+            }
+
+            let top = stack.last_mut().unwrap();
+            if top.0 < lcp {
+                stack.push((lcp, vec![std::mem::take(&mut last)]));
+            } else {
+                top.1.push(std::mem::take(&mut last));
+            }
+            if let Some(&suffix) = self.suffixes.get(r) {
+                last = vec![suffix];
+            }
+        }
+
+        matches.sort_by_key(|m| (m.s1_char_index, m.s2_char_index));
+        matches
+    }
+
+    /// Returns the maximal unique matches (MUMs) of s1 and s2 that are at least `min_len`
+    /// characters long. A maximal unique match is a maximal shared substring (see
+    /// `maximal_substrings()`) that occurs exactly once in s1 and exactly once in s2. The matches
+    /// are sorted by their position in s1.
+    pub fn maximal_unique_matches(&self, min_len: usize) -> Vec<Match<'a>> {
+        // There are no meaningful matches of length 0
+        let min_len = min_len.max(1);
+
+        // A substring that occurs exactly twice is shared by exactly two adjacent suffixes, so their
+        // common prefix has to be longer than the ones they share with their other neighbors.
+        let mut matches: Vec<Match<'a>> = (1..self.suffixes.len())
+            .filter(|&r| {
+                let len = self.lcp[r];
+                len >= min_len && self.lcp[r - 1] < len && self.lcp.get(r + 1).is_none_or(|&next| next < len)
+            })
+            .filter(|&r| {
+                let left = self.left_char(self.suffixes[r]);
+                left.is_none() || left != self.left_char(self.suffixes[r - 1])
+            })
+            .filter_map(|r| self.cross_match(self.suffixes[r - 1], self.suffixes[r], self.lcp[r]))
+            .collect();
+
+        matches.sort_by_key(|m| m.s1_char_index);
+        matches
+    }
+
+    // Reports the pairs of suffixes from different children of an lcp-interval that can't be
+    // extended to the left
+    fn report_maximal(&self, len: usize, children: &[Vec<usize>], matches: &mut Vec<Match<'a>>) {
+        for (c, child) in children.iter().enumerate() {
+            for other in &children[..c] {
+                for &a in child {
+                    for &b in other {
+                        let left_a = self.left_char(a);
+                        if left_a.is_some() && left_a == self.left_char(b) {
+                            continue;
+                        }
+                        if let Some(m) = self.cross_match(a, b, len) {
+                            matches.push(m);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the maximal unique matches (MUMs) of s1 and s2 that are at least `min_len` characters
+/// long, i.e. the substrings that occur exactly once in each string and can't be extended to the
+/// left or to the right. MUMs are commonly used as anchors when aligning genomes. The matches hold
+/// the positions of the substring in both strings and are sorted by their position in s1.
+///
+/// Every MUM starts with a common substring of `min_len` characters, so those are used as seeds:
+/// the `min_len`-length windows of both strings are stuck in hash maps (using the firefox hashing
+/// algorithm) along with every position they occur at. A seed that occurs once in each string and
+/// can't be extended to the left is extended to the right into a maximal match, which is unique
+/// since it contains the seed. This takes ~O(n) time (where n is the combined length of the
+/// strings). As soon as a common seed occurs more than once in either string (e.g. in repeats like
+/// poly-A runs), checking which of its matches are unique could take quadratic time, so the MUMs
+/// are found with a `GeneralizedSuffixArray` instead. Use
+/// `GeneralizedSuffixArray::maximal_unique_matches()` directly to query several values of
+/// `min_len` on the same strings.
+pub fn maximal_unique_matches<'a>(s1: &'a str, s2: &'a str, min_len: usize) -> Vec<Match<'a>> {
+    // There are no meaningful matches of length 0
+    let min_len = min_len.max(1);
+    let (s1_offsets, c1): (Vec<usize>, Vec<char>) = s1.char_indices().unzip();
+    let (s2_offsets, c2): (Vec<usize>, Vec<char>) = s2.char_indices().unzip();
+    let byte_offset = |offsets: &[usize], source: &str, i: usize| offsets.get(i).copied().unwrap_or(source.len());
+    let kgrams1 = build_fx_positions_table(c1.as_slice(), c1.len(), min_len);
+    let kgrams2 = build_fx_positions_table(c2.as_slice(), c2.len(), min_len);
+
+    let mut matches = Vec::new();
+    for (seed, positions1) in &kgrams1 {
+        let Some(positions2) = kgrams2.get(seed) else {
+            continue;
+        };
+        let (&[i], &[j]) = (positions1.as_slice(), positions2.as_slice()) else {
+            return GeneralizedSuffixArray::new(s1, s2).maximal_unique_matches(min_len);
+        };
+        if i > 0 && j > 0 && c1[i - 1] == c2[j - 1] {
+            continue;
+        }
+        let len = min_len + c1[i + min_len..].iter().zip(&c2[j + min_len..]).take_while(|(a, b)| a == b).count();
+        let s1_start = byte_offset(&s1_offsets, s1, i);
+        let text = &s1[s1_start..byte_offset(&s1_offsets, s1, i + len)];
+        matches.push(Match::new(text, (s1_start, i), (byte_offset(&s2_offsets, s2, j), j)));
+    }
+
+    matches.sort_by_key(|m| m.s1_char_index);
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use crate::{longest_common_substring, substring};
+    use crate::suffix_array::{maximal_unique_matches, GeneralizedSuffixArray};
+
+    // Slow but simple reference implementation that tries extending every pair of matching
+    // characters that can't be extended to the left. Returns (s1 char index, s2 char index, length)
+    // tuples.
+    fn maximal_substrings_reference_impl(s1: &str, s2: &str, min_len: usize) -> Vec<(usize, usize, usize)> {
+        let c1: Vec<char> = s1.chars().collect();
+        let c2: Vec<char> = s2.chars().collect();
+        let mut matches = Vec::new();
+        for i in 0..c1.len() {
+            for j in 0..c2.len() {
+                if i > 0 && j > 0 && c1[i - 1] == c2[j - 1] {
+                    continue;
+                }
+                let len = c1[i..].iter().zip(&c2[j..]).take_while(|(a, b)| a == b).count();
+                if len >= min_len.max(1) {
+                    matches.push((i, j, len));
+                }
+            }
+        }
+        matches
+    }
+
+    #[test]
+    fn test_common_substring() {
+        let s1 = "This is a test string. - Normal Person";
+        let s2 = "Here be another test string. Yaargh. - Pirate";
+        let gsa = GeneralizedSuffixArray::new(s1, s2);
+
+        let m = gsa.common_substring(5).unwrap();
+        assert_eq!(m.text, " stri");
+        assert_eq!(&s1[m.s1_range.clone()], m.text);
+        assert_eq!(&s2[m.s2_range.clone()], m.text);
+        assert!(gsa.has_common_substring(14));
+        assert!(!gsa.has_common_substring(15));
+        assert_eq!(gsa.longest_common_substring().unwrap().text, " test string. ");
+    }
+
+    // Keeps the maximal shared substrings that occur exactly once in each string
+    fn maximal_unique_matches_reference_impl(s1: &str, s2: &str, min_len: usize) -> Vec<(usize, usize, usize)> {
+        let count = |s: &str, sub: &[char]| {
+            let chars: Vec<char> = s.chars().collect();
+            chars.windows(sub.len()).filter(|w| *w == sub).count()
+        };
+        let c1: Vec<char> = s1.chars().collect();
+        maximal_substrings_reference_impl(s1, s2, min_len).into_iter()
+            .filter(|&(i, _, len)| {
+                let sub = &c1[i..i + len];
+                count(s1, sub) == 1 && count(s2, sub) == 1
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_maximal_unique_matches() {
+        let s1 = "ACGTTGCAACGTAGGCTTAC";
+        let s2 = "TTGCAACCTAGGCTTACGTA";
+
+        let matches: Vec<_> = maximal_unique_matches(s1, s2, 4).into_iter()
+            .map(|m| (m.text, m.s1_range, m.s2_range))
+            .collect();
+        assert_eq!(matches, vec![("TTGCAAC", 3..10, 0..7), ("ACGTA", 8..13, 15..20), ("TAGGCTTAC", 11..20, 8..17)]);
+    }
+
+    #[test]
+    fn test_maximal_substrings() {
+        let s1 = "›It costs €10 for this item… or €12 for that one";
+        let s2 = "Pay €12 for that now";
+        let gsa = GeneralizedSuffixArray::new(s1, s2);
+
+        let matches: Vec<&str> = gsa.maximal_substrings(4).into_iter().map(|m| m.text).collect();
+        assert_eq!(matches, vec![" for th", " €12 for that "]);
+        assert_eq!(GeneralizedSuffixArray::new("", s2).maximal_substrings(1), vec![]);
+    }
+
+    proptest! {
+        #[test]
+        fn test_common_substring_against_substring(
+            s1 in "[abc€]{0,20}",
+            s2 in "[abc€]{0,20}",
+            k in 0..6usize,
+        ) {
+            let gsa = GeneralizedSuffixArray::new(&s1, &s2);
+            let r = gsa.common_substring(k);
+            assert_eq!(r.is_some(), substring(&s1, &s2, k).is_some());
+            if let Some(m) = r {
+                assert_eq!(m.char_len(), k);
+                assert_eq!(&s1[m.s1_range], m.text);
+                assert_eq!(&s2[m.s2_range], m.text);
+            }
+        }
+
+        #[test]
+        fn test_longest_common_substring_length(
+            s1 in "[abc€]{0,20}",
+            s2 in "[abc€]{0,20}",
+        ) {
+            let gsa = GeneralizedSuffixArray::new(&s1, &s2);
+            assert_eq!(gsa.longest_common_substring().map(|m| m.char_len()), longest_common_substring(&s1, &s2).map(|m| m.char_len()));
+        }
+
+        #[test]
+        fn test_maximal_substrings_against_reference(
+            s1 in "[abc€]{0,20}",
+            s2 in "[abc€]{0,20}",
+            min_len in 0..4usize,
+        ) {
+            let gsa = GeneralizedSuffixArray::new(&s1, &s2);
+            let matches: Vec<_> = gsa.maximal_substrings(min_len).into_iter()
+                .map(|m| (m.s1_char_index, m.s2_char_index, m.char_len()))
+                .collect();
+            assert_eq!(matches, maximal_substrings_reference_impl(&s1, &s2, min_len));
+        }
+
+        #[test]
+        fn test_maximal_unique_matches_against_reference(
+            s1 in "[ACGT]{0,20}",
+            s2 in "[ACGT]{0,20}",
+            min_len in 0..4usize,
+        ) {
+            let expected = maximal_unique_matches_reference_impl(&s1, &s2, min_len);
+            let matches: Vec<_> = maximal_unique_matches(&s1, &s2, min_len).into_iter()
+                .map(|m| (m.s1_char_index, m.s2_char_index, m.char_len()))
+                .collect();
+            assert_eq!(matches, expected);
+            let matches: Vec<_> = GeneralizedSuffixArray::new(&s1, &s2).maximal_unique_matches(min_len).into_iter()
+                .map(|m| (m.s1_char_index, m.s2_char_index, m.char_len()))
+                .collect();
+            assert_eq!(matches, expected);
+        }
+
+        #[test]
+        // Highly repetitive inputs have seeds with many occurrences, which have to be handled
+        // without trying every pair of them.
+        fn test_maximal_unique_matches_repetitive(
+            s1 in "[A]{0,200}",
+            s2 in "[A]{0,200}",
+            min_len in 0..4usize,
+        ) {
+            let matches: Vec<_> = maximal_unique_matches(&s1, &s2, min_len).into_iter()
+                .map(|m| (m.s1_char_index, m.s2_char_index, m.char_len()))
+                .collect();
+            // A run of As only occurs once in a run of As of the same length.
+            let expected = if s1.len() == s2.len() && s1.len() >= min_len.max(1) { vec![(0, 0, s1.len())] } else { vec![] };
+            assert_eq!(matches, expected);
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use rustc_hash::{FxHashMap, FxHashSet};
+use crate::implementations::Match;
+
+struct State {
+    // Length of the longest substring that ends in this state
+    len: usize,
+    // Suffix link (None for the initial state)
+    link: Option<usize>,
+    next: FxHashMap<char, usize>,
+    // Char index of the last character of the first occurrence of the substrings that end in this
+    // state
+    first_end: usize,
+}
+
+/// Suffix automaton of a text, i.e. the smallest automaton that accepts exactly the suffixes of the
+/// text. Every substring of the text corresponds to a path from the initial state, so another string
+/// can be run through the automaton to find its common substrings with the text in a single linear
+/// scan. Unlike the hash tables used by the other implementations, the automaton doesn't depend on
+/// k so it only needs to be built once to answer queries for any k.
+///
+/// The automaton is built over characters (unicode scalar values) in ~O(n) time and has at most
+/// 2n - 1 states (where n is the length of the text). Matches returned by the automaton use the
+/// text as s1 and the queried string as s2.
+pub struct SuffixAutomaton<'a> {
+    text: &'a str,
+    // Byte offset of each character in the text
+    offsets: Vec<usize>,
+    states: Vec<State>,
+}
+
+impl<'a> SuffixAutomaton<'a> {
+    /// Builds the suffix automaton of `text` using the standard online construction.
+    pub fn new(text: &'a str) -> Self {
+        let mut offsets = Vec::new();
+        let mut states = vec![State { len: 0, link: None, next: FxHashMap::default(), first_end: 0 }];
+        // The state that the whole text read so far ends in
+        let mut last = 0;
+
+        for (pos, (i, c)) in text.char_indices().enumerate() {
+            offsets.push(i);
+            let cur = states.len();
+            states.push(State { len: states[last].len + 1, link: None, next: FxHashMap::default(), first_end: pos });
+
+            // Every suffix of the text read so far that can't be extended by c yet now can be.
+            let mut p = Some(last);
+            while let Some(q) = p {
+                if states[q].next.contains_key(&c) {
+                    break;
+                }
+                states[q].next.insert(c, cur);
+                p = states[q].link;
+            }
+
+            match p {
+                None => states[cur].link = Some(0),
+                Some(p) => {
+                    let q = states[p].next[&c];
+                    if states[p].len + 1 == states[q].len {
+                        states[cur].link = Some(q);
+                    } else {
+                        // The substrings ending in q no longer all occur at the same positions, so
+                        // q has to be split by cloning it.
+                        let clone = states.len();
+                        states.push(State {
+                            len: states[p].len + 1,
+                            link: states[q].link,
+                            next: states[q].next.clone(),
+                            first_end: states[q].first_end,
+                        });
+                        let mut p = Some(p);
+                        while let Some(r) = p {
+                            if states[r].next.get(&c) != Some(&q) {
+                                break;
+                            }
+                            states[r].next.insert(c, clone);
+                            p = states[r].link;
+                        }
+                        states[q].link = Some(clone);
+                        states[cur].link = Some(clone);
+                    }
+                },
+            }
+            last = cur;
+        }
+
+        SuffixAutomaton {
+            text,
+            offsets,
+            states,
+        }
+    }
+
+    /// Returns the text the automaton was built from.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Returns the number of states in the automaton.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Returns true if the automaton only has its initial state (the text is empty).
+    pub fn is_empty(&self) -> bool {
+        self.states.len() == 1
+    }
+
+    // Extends a match of length `len` that ends in state `v` by the character `c`. If the match
+    // can't be extended, it is shortened (by following suffix links) until it can. Returns the new
+    // state and match length.
+    fn step(&self, mut v: usize, mut len: usize, c: char) -> (usize, usize) {
+        loop {
+            if let Some(&next) = self.states[v].next.get(&c) {
+                return (next, len + 1);
+            }
+            match self.states[v].link {
+                Some(link) => {
+                    v = link;
+                    len = self.states[v].len;
+                },
+                None => return (0, 0),
+            }
+        }
+    }
+
+    // Returns the (byte offset, char index) of the first occurrence in the text of the substring of
+    // length `len` that ends in state `v` (or in one of the states it links to).
+    fn first_occurrence(&self, mut v: usize, len: usize) -> (usize, usize) {
+        // Shorter substrings can belong to states further up the suffix links, which may occur
+        // earlier in the text.
+        while let Some(link) = self.states[v].link {
+            if self.states[link].len < len {
+                break;
+            }
+            v = link;
+        }
+        let start = self.states[v].first_end + 1 - len;
+        (self.offsets[start], start)
+    }
+
+    // Builds the match for the `len` characters of `other` that end at byte offset `end` and char
+    // index `end_char` (exclusive), given the state they end in.
+    fn build_match<'b>(&self, other: &'b str, v: usize, len: usize, end: usize, end_char: usize) -> Match<'b> {
+        let start = other[..end].char_indices().rev().nth(len - 1).unwrap().0;
+        Match::new(&other[start..end], self.first_occurrence(v, len), (start, end_char - len))
+    }
+
+    /// Returns the first substring of length k in `other` that also occurs in the text, or None if
+    /// there is no such substring.
+    pub fn find<'b>(&self, other: &'b str, k: usize) -> Option<Match<'b>> {
+        // Trivial to have matching substrings of length 0
+        if k == 0 {
+            return Some(Match::empty());
+        }
+
+        let (mut v, mut len) = (0, 0);
+        for (j, (i, c)) in other.char_indices().enumerate() {
+            (v, len) = self.step(v, len, c);
+            if len >= k {
+                return Some(self.build_match(other, v, k, i + c.len_utf8(), j + 1));
+            }
+        }
+        None
+    }
+
+    /// Returns every distinct substring of length k in `other` that also occurs in the text, in the
+    /// order they first occur in `other` (at their first occurrence in both strings).
+    pub fn find_all<'b>(&self, other: &'b str, k: usize) -> Vec<Match<'b>> {
+        // Trivial to have matching substrings of length 0
+        if k == 0 {
+            return vec![Match::empty()];
+        }
+
+        let mut matches = Vec::new();
+        // Char indices of the first occurrences in the text of the substrings found so far, which
+        // identify each distinct substring.
+        let mut found = FxHashSet::default();
+        let (mut v, mut len) = (0, 0);
+        for (j, (i, c)) in other.char_indices().enumerate() {
+            (v, len) = self.step(v, len, c);
+            if len >= k && found.insert(self.first_occurrence(v, k).1) {
+                matches.push(self.build_match(other, v, k, i + c.len_utf8(), j + 1));
+            }
+        }
+        matches
+    }
+
+    /// Returns if `other` has a substring of length k in common with the text.
+    pub fn has_common_substring(&self, other: &str, k: usize) -> bool {
+        self.find(other, k).is_some()
+    }
+
+    /// Returns the longest common substring of the text and `other` (the first one in `other` if
+    /// there are several), or None if they don't have any characters in common.
+    pub fn longest_common_substring<'b>(&self, other: &'b str) -> Option<Match<'b>> {
+        // Holds the (length, state, end byte offset, end char index) of the longest match so far
+        let mut longest = None;
+        let (mut v, mut len) = (0, 0);
+        for (j, (i, c)) in other.char_indices().enumerate() {
+            (v, len) = self.step(v, len, c);
+            if len > longest.map_or(0, |(l, _, _, _)| l) {
+                longest = Some((len, v, i + c.len_utf8(), j + 1));
+            }
+        }
+        longest.map(|(len, v, end, end_char)| self.build_match(other, v, len, end, end_char))
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use proptest::prelude::*;
+    use crate::implementations::_naive_substring;
+    use crate::longest_common_substring;
+    use crate::suffix_automaton::SuffixAutomaton;
+
+    #[test]
+    fn test_suffix_automaton_find() {
+        let s1 = "This is a test string. - Normal Person";
+        let s2 = "Here be another test string. Yaargh. - Pirate";
+        let automaton = SuffixAutomaton::new(s1);
+
+        let m = automaton.find(s2, 5).unwrap();
+        assert_eq!(m.text, " test");
+        assert_eq!(m.s1_range, 9..14);
+        assert_eq!(m.s2_range, 15..20);
+        assert!(automaton.has_common_substring(s2, 14));
+        assert!(!automaton.has_common_substring(s2, 15));
+        assert!(!automaton.has_common_substring("Who lives in a pineapple under the sea? - Patchy", 5));
+    }
+
+    #[test]
+    fn test_suffix_automaton_longest_common_substring() {
+        let s1 = "›It costs €10 for this item… or €12 for that one";
+        let s2 = "Pay €12 for that now";
+        let automaton = SuffixAutomaton::new(s1);
+
+        let m = automaton.longest_common_substring(s2).unwrap();
+        assert_eq!(m.text, " €12 for that ");
+        assert_eq!(&s1[m.s1_range], m.text);
+        assert_eq!(&s2[m.s2_range], m.text);
+        assert_eq!(m.s2_char_index, 3);
+        assert_eq!(automaton.longest_common_substring("xyz"), None);
+        assert_eq!(SuffixAutomaton::new("").longest_common_substring(s2), None);
+    }
+
+    #[test]
+    fn test_suffix_automaton_size() {
+        let text = "abcbcabbcabcabc";
+        let automaton = SuffixAutomaton::new(text);
+        assert!(automaton.len() < 2 * text.len());
+        assert!(SuffixAutomaton::new("").is_empty());
+    }
+
+    proptest! {
+        #[test]
+        // The naive implementation also returns the first match in s2, along with where that
+        // substring first occurs in s1.
+        fn test_suffix_automaton_against_naive(
+            s1 in "[abc€]{0,20}",
+            s2 in "[abc€]{0,20}",
+            k in 0..6usize,
+        ) {
+            let automaton = SuffixAutomaton::new(&s1);
+            assert_eq!(automaton.find(&s2, k), _naive_substring(&s1, &s2, k));
+        }
+
+        #[test]
+        fn test_suffix_automaton_longest_common_substring_length(
+            s1 in "[abc€]{0,20}",
+            s2 in "[abc€]{0,20}",
+        ) {
+            let automaton = SuffixAutomaton::new(&s1);
+            let r = automaton.longest_common_substring(&s2);
+            assert_eq!(r.as_ref().map(|m| m.char_len()), longest_common_substring(&s1, &s2).map(|m| m.char_len()));
+            if let Some(m) = r {
+                assert_eq!(&s1[m.s1_range], m.text);
+                assert_eq!(&s2[m.s2_range], m.text);
+            }
+        }
+    }
+}
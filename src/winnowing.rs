@@ -0,0 +1,213 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use rustc_hash::FxHashMap;
+use crate::implementations::{build_rolling_polynomial_substring_with_salt, offset_in};
+
+/// Salt used for the rolling polynomial hashes of the fingerprints. It is fixed (rather than random
+/// like for the substring search implementations) so fingerprints can be stored and compared
+/// across runs, e.g. to check new documents against a corpus. Odd salts keep the first bytes of
+/// long k-grams from being shifted out of the hash.
+pub const WINNOWING_SALT: u64 = 0x9e3779b97f4a7c15;
+
+/// Hash of a k-gram that was selected by `winnow()`, along with where the k-gram occurs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// Hash of the k-gram.
+    pub hash: u64,
+    /// Byte range of the k-gram in the text.
+    pub byte_range: Range<usize>,
+    /// Range (in characters) of the k-gram in the text.
+    pub char_range: Range<usize>,
+}
+
+/// A passage that two documents appear to share, found by matching their fingerprints.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SharedPassage {
+    /// Byte range of the passage in the first document.
+    pub s1_range: Range<usize>,
+    /// Byte range of the passage in the second document.
+    pub s2_range: Range<usize>,
+    /// Index (in characters) at which the passage starts in the first document.
+    pub s1_char_index: usize,
+    /// Index (in characters) at which the passage starts in the second document.
+    pub s2_char_index: usize,
+    /// Length of the passage in characters.
+    pub char_len: usize,
+    /// Number of matching fingerprints that make up the passage.
+    pub fingerprints: usize,
+}
+
+/// Returns the fingerprints of `text` using (robust) winnowing, as used by MOSS. Each window of
+/// `window` consecutive k-grams is represented by the k-gram with the smallest hash. Ties are broken
+/// in favor of the k-gram picked for the previous window if it's still in the window, or the
+/// rightmost one otherwise, which keeps repetitive text (like "aaaa...") from producing a
+/// fingerprint for every k-gram. Each picked k-gram is only reported once, so only about
+/// 2/(window+1) of the k-grams end up as fingerprints.
+///
+/// This guarantees that any substring of at least window + k - 1 characters that two texts share
+/// produces at least one matching fingerprint in both, while shared substrings of fewer than k
+/// characters (e.g. common words) never do. The k-grams are hashed with the rolling polynomial hash
+/// using `WINNOWING_SALT`.
+pub fn winnow(text: &str, k: usize, window: usize) -> Vec<Fingerprint> {
+    let n_chars = text.chars().count();
+    // There are no k-grams if the string has fewer than k characters. We also don't fingerprint
+    // empty k-grams.
+    if k == 0 || n_chars < k {
+        return Vec::new();
+    }
+    let window = window.max(1);
+    let n_kgrams = n_chars - k + 1;
+
+    let mut sub_fn = build_rolling_polynomial_substring_with_salt(text, k, WINNOWING_SALT);
+    // K-grams in the current window that could still be picked for a later window, i.e. with
+    // strictly increasing hashes. The front is the rightmost k-gram with the smallest hash.
+    let mut candidates: VecDeque<(usize, &str, u64)> = VecDeque::with_capacity(window + 1);
+    // The k-gram picked for the previous window
+    let mut picked: Option<(usize, u64)> = None;
+    let mut fingerprints = Vec::new();
+
+    for i in 0..n_kgrams {
+        let (sub, hash) = sub_fn();
+        while candidates.back().is_some_and(|&(_, _, h)| h >= hash) {
+            candidates.pop_back();
+        }
+        candidates.push_back((i, sub, hash));
+        if candidates.front().is_some_and(|&(j, _, _)| j + window <= i) {
+            candidates.pop_front();
+        }
+
+        // Only full windows pick a k-gram
+        if i + 1 < window.min(n_kgrams) {
+            continue;
+        }
+        let &(j, sub, hash) = candidates.front().unwrap();
+        let still_picked = picked.is_some_and(|(p, h)| p + window > i && h == hash);
+        if !still_picked {
+            picked = Some((j, hash));
+            let start = offset_in(text, sub);
+            fingerprints.push(Fingerprint {
+                hash,
+                byte_range: start..start + sub.len(),
+                char_range: j..j + k,
+            });
+        }
+    }
+    fingerprints
+}
+
+/// Given the fingerprints of two documents (from `winnow()` with the same k and window), returns
+/// the passages they share. Matching fingerprints whose k-grams overlap or touch at the same
+/// offset between the documents are merged into a single passage. The passages are sorted by their
+/// position in the first document, then in the second one.
+///
+/// Any substring of at least window + k - 1 characters that the documents share is reported (as one
+/// or more passages), and no passage is shorter than k characters. Since only the hashes are
+/// compared, a hash collision could report a passage that isn't actually shared, but this is very
+/// unlikely with 64-bit hashes.
+pub fn compare_fingerprints(fingerprints1: &[Fingerprint], fingerprints2: &[Fingerprint]) -> Vec<SharedPassage> {
+    let mut by_hash: FxHashMap<u64, Vec<&Fingerprint>> = FxHashMap::default();
+    for f in fingerprints2 {
+        by_hash.entry(f.hash).or_default().push(f);
+    }
+
+    // Pairs of matching fingerprints, sorted by the offset between the documents (the diagonal) and
+    // then by their position in the first document so the ones to merge are adjacent.
+    let mut pairs: Vec<(&Fingerprint, &Fingerprint)> = fingerprints1.iter()
+        .flat_map(|f1| by_hash.get(&f1.hash).into_iter().flatten().map(move |f2| (f1, *f2)))
+        .collect();
+    pairs.sort_by_key(|(f1, f2)| (f2.char_range.start as isize - f1.char_range.start as isize, f1.char_range.start));
+
+    let mut passages: Vec<SharedPassage> = Vec::new();
+    let mut diagonal = None;
+    for (f1, f2) in pairs {
+        let d = f2.char_range.start as isize - f1.char_range.start as isize;
+        let extends_last = diagonal == Some(d) && passages.last().is_some_and(|p| {
+            f1.char_range.start <= p.s1_char_index + p.char_len
+        });
+
+        if extends_last {
+            let p = passages.last_mut().unwrap();
+            p.s1_range.end = p.s1_range.end.max(f1.byte_range.end);
+            p.s2_range.end = p.s2_range.end.max(f2.byte_range.end);
+            p.char_len = p.char_len.max(f1.char_range.end - p.s1_char_index);
+            p.fingerprints += 1;
+        } else {
+            passages.push(SharedPassage {
+                s1_range: f1.byte_range.clone(),
+                s2_range: f2.byte_range.clone(),
+                s1_char_index: f1.char_range.start,
+                s2_char_index: f2.char_range.start,
+                char_len: f1.char_range.len(),
+                fingerprints: 1,
+            });
+        }
+        diagonal = Some(d);
+    }
+
+    passages.sort_by_key(|p| (p.s1_char_index, p.s2_char_index));
+    passages
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use crate::winnowing::{compare_fingerprints, winnow};
+
+    #[test]
+    fn test_winnow() {
+        let text = "›It costs €10 for this item… or €12 for that one";
+        let (k, window) = (4, 5);
+        let fingerprints = winnow(text, k, window);
+
+        // Every window has to contain a fingerprint, so they can't be more than `window` k-grams
+        // apart
+        assert!(fingerprints[0].char_range.start < window);
+        assert!(fingerprints.windows(2).all(|pair| {
+            pair[0].char_range.start < pair[1].char_range.start && pair[1].char_range.start - pair[0].char_range.start <= window
+        }));
+        for f in &fingerprints {
+            assert_eq!(text[f.byte_range.clone()].chars().count(), k);
+            assert_eq!(text.char_indices().nth(f.char_range.start).unwrap().0, f.byte_range.start);
+        }
+
+        // Repetitive text only needs a fingerprint every `window` k-grams
+        assert_eq!(winnow(&"a".repeat(40), k, window).len(), 7);
+        assert_eq!(winnow("abc", k, window), vec![]);
+    }
+
+    #[test]
+    fn test_compare_fingerprints() {
+        let s1 = "We hold these truths to be self-evident, that all men are created equal.";
+        let s2 = "As the founders wrote: we hold these truths to be self-evident.";
+        let (k, window) = (6, 4);
+
+        let passages = compare_fingerprints(&winnow(s1, k, window), &winnow(s2, k, window));
+        assert_eq!(passages.len(), 1);
+        let p = &passages[0];
+        assert_eq!(&s1[p.s1_range.clone()], &s2[p.s2_range.clone()]);
+        assert!("e hold these truths to be self-evident".contains(&s1[p.s1_range.clone()]));
+        assert!(p.char_len >= 30);
+    }
+
+    proptest! {
+        #[test]
+        // Any shared substring of at least window + k - 1 characters has to be reported.
+        fn test_compare_fingerprints_guarantee(
+            prefix1 in "[abc€]{0,20}",
+            prefix2 in "[abc€]{0,20}",
+            shared in "[abc€]{12}",
+            suffix in "[abc€]{0,20}",
+            k in 1..6usize,
+            window in 1..8usize,
+        ) {
+            let s1 = format!("{}{}", prefix1, shared);
+            let s2 = format!("{}{}{}", prefix2, shared, suffix);
+            let passages = compare_fingerprints(&winnow(&s1, k, window), &winnow(&s2, k, window));
+            assert!(!passages.is_empty());
+            for p in passages {
+                assert!(p.char_len >= k);
+                assert_eq!(&s1[p.s1_range], &s2[p.s2_range]);
+            }
+        }
+    }
+}